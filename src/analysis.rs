@@ -0,0 +1,1533 @@
+// Trajectory analysis: spectral and geometric
+// diagnostics on sampled attractors.
+
+use crate::catalog::Entry;
+use crate::classify::{self, ClassifyConfig, Dynamics};
+use crate::lyapunov::{cross, LyapData};
+use crate::ode::{self, Coeffs, State, NTERMS};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::io::Write;
+use std::path::Path;
+
+/// In-place iterative radix-2 FFT.
+/// Length must be a power of two.
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let ang = -2.0 * PI / len as f64;
+        let (wr, wi) = (ang.cos(), ang.sin());
+        for start in (0..n).step_by(len) {
+            let (mut cr, mut ci) = (1.0, 0.0);
+            for k in 0..len / 2 {
+                let a = start + k;
+                let b = a + len / 2;
+                let tr = re[b] * cr - im[b] * ci;
+                let ti = re[b] * ci + im[b] * cr;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+                let t = cr * wr - ci * wi;
+                ci = cr * wi + ci * wr;
+                cr = t;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Hann window weight for sample i of n.
+#[inline]
+fn hann(i: usize, n: usize) -> f64 {
+    let x = PI * i as f64 / (n - 1) as f64;
+    x.sin() * x.sin()
+}
+
+/// One-sided Hann-windowed periodogram.
+/// Mean is removed; input is zero-padded to
+/// the next power of two. Returns (freq, power).
+pub fn power_spectrum(
+    series: &[f64],
+    dt: f64,
+) -> Vec<(f64, f64)> {
+    let n = series.len();
+    if n < 2 {
+        return Vec::new();
+    }
+    let m = n.next_power_of_two();
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let mut re = vec![0.0; m];
+    let mut im = vec![0.0; m];
+    let mut wsum = 0.0;
+    for (i, &v) in series.iter().enumerate() {
+        let w = hann(i, n);
+        re[i] = (v - mean) * w;
+        wsum += w * w;
+    }
+    fft(&mut re, &mut im);
+    let df = 1.0 / (m as f64 * dt);
+    let k = dt / wsum;
+    (0..=m / 2)
+        .map(|i| {
+            let p = re[i] * re[i] + im[i] * im[i];
+            (i as f64 * df, p * k)
+        })
+        .collect()
+}
+
+/// Write (freq, power) pairs as CSV.
+pub fn export_psd_csv(
+    psd: &[(f64, f64)],
+    path: &Path,
+) -> std::io::Result<()> {
+    let f = std::fs::File::create(path)?;
+    let mut w = std::io::BufWriter::new(f);
+    writeln!(w, "freq,power")?;
+    for (f, p) in psd {
+        writeln!(w, "{f},{p}")?;
+    }
+    w.flush()
+}
+
+/// Classify the motion from each cell centre of a
+/// grid³ lattice over the cube [-extent, extent]³.
+/// Several bounded kinds signal multistability.
+pub fn basin_scan(
+    c: &Coeffs,
+    grid: usize,
+    extent: f64,
+) -> Vec<(State, Dynamics)> {
+    let p = ClassifyConfig::default();
+    let h = 2.0 * extent / grid as f64;
+    let at = |i: usize| -extent + (i as f64 + 0.5) * h;
+    (0..grid * grid * grid)
+        .into_par_iter()
+        .map(|k| {
+            let s0 =
+                [at(k / (grid * grid)), at(k / grid % grid), at(k % grid)];
+            (s0, classify::classify_from_with(c, &s0, &p))
+        })
+        .collect()
+}
+
+/// Delay vectors [s_i, s_{i+τ}, .., s_{i+(dim-1)τ}]
+/// for every i with a full window.
+pub fn delay_embed(series: &[f64], dim: usize, tau: usize) -> Vec<Vec<f64>> {
+    let span = dim.saturating_sub(1) * tau;
+    if dim == 0 || series.len() <= span {
+        return Vec::new();
+    }
+    (0..series.len() - span)
+        .map(|i| (0..dim).map(|k| series[i + k * tau]).collect())
+        .collect()
+}
+
+/// Bins per axis of the mutual-information
+/// histogram: equal-width over the series range.
+/// Few enough to keep cells populated for a few
+/// thousand samples (≈ 10 per cell at 2500).
+const MI_BINS: usize = 16;
+
+/// Mutual information (nats) between s_i and s_{i+τ}.
+fn mutual_information(series: &[f64], tau: usize) -> f64 {
+    let n = series.len() - tau;
+    let (lo, hi) = series
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(a, b), &v| (a.min(v), b.max(v)));
+    let w = if hi > lo { (hi - lo) / MI_BINS as f64 } else { 1.0 };
+    let bin = |v: f64| (((v - lo) / w) as usize).min(MI_BINS - 1);
+    let mut joint = vec![0usize; MI_BINS * MI_BINS];
+    let (mut pa, mut pb) = ([0usize; MI_BINS], [0usize; MI_BINS]);
+    for i in 0..n {
+        let (a, b) = (bin(series[i]), bin(series[i + tau]));
+        joint[a * MI_BINS + b] += 1;
+        pa[a] += 1;
+        pb[b] += 1;
+    }
+    let n = n as f64;
+    let mut mi = 0.0;
+    for (k, &c) in joint.iter().enumerate() {
+        if c > 0 {
+            let (a, b) = (pa[k / MI_BINS] as f64, pb[k % MI_BINS] as f64);
+            mi += c as f64 / n * (c as f64 * n / (a * b)).ln();
+        }
+    }
+    mi
+}
+
+/// Embedding delay: first local minimum of the
+/// time-delayed mutual information over 1..=max_tau
+/// (see MI_BINS), else the lag of its smallest value.
+pub fn mutual_information_tau(series: &[f64], max_tau: usize) -> usize {
+    let max_tau = max_tau.min(series.len().saturating_sub(2));
+    if max_tau < 1 {
+        return 1;
+    }
+    let mi: Vec<f64> = (1..=max_tau)
+        .map(|t| mutual_information(series, t))
+        .collect();
+    let first = (1..mi.len().saturating_sub(1))
+        .find(|&k| mi[k] < mi[k - 1] && mi[k] <= mi[k + 1]);
+    let best = first.unwrap_or_else(|| {
+        (0..mi.len())
+            .min_by(|&a, &b| mi[a].total_cmp(&mi[b]))
+            .unwrap_or(0)
+    });
+    best + 1
+}
+
+/// Longest trajectory fed to the O(N²) recurrence
+/// measures; longer ones are strided to fit.
+pub const RQA_MAX_POINTS: usize = 2000;
+
+/// Minimum diagonal line length for determinism.
+const RQA_LMIN: usize = 2;
+
+/// Recurrent pairs i < j (|p_i - p_j| < eps) and
+/// how many lie on diagonal lines of RQA_LMIN or
+/// more, plus the pair count.
+fn recurrences(traj: &[State], eps: f64) -> (usize, usize, usize) {
+    let stride = traj.len().div_ceil(RQA_MAX_POINTS).max(1);
+    let t: Vec<State> = traj.iter().step_by(stride).copied().collect();
+    let n = t.len();
+    let (mut rec, mut lined) = (0, 0);
+    for k in 1..n {
+        let mut run = 0;
+        for i in 0..=n - k {
+            if i < n - k && dist(&t[i], &t[i + k]) < eps {
+                run += 1;
+                continue;
+            }
+            rec += run;
+            if run >= RQA_LMIN {
+                lined += run;
+            }
+            run = 0;
+        }
+    }
+    (rec, lined, n * n.saturating_sub(1) / 2)
+}
+
+/// Fraction of off-diagonal recurrence matrix
+/// entries within eps (see RQA_MAX_POINTS).
+pub fn recurrence_rate(traj: &[State], eps: f64) -> f64 {
+    let (rec, _, pairs) = recurrences(traj, eps);
+    if pairs == 0 {
+        return 0.0;
+    }
+    rec as f64 / pairs as f64
+}
+
+/// Determinism: share of recurrences on diagonal
+/// lines of length >= 2. Near 1 for periodic
+/// motion, lower for chaos, near 0 for noise.
+pub fn recurrence_determinism(traj: &[State], eps: f64) -> f64 {
+    let (rec, lined, _) = recurrences(traj, eps);
+    if rec == 0 {
+        return 0.0;
+    }
+    lined as f64 / rec as f64
+}
+
+/// Write trajectory points as an ASCII PLY cloud.
+pub fn export_ply(traj: &[State], path: &Path) -> std::io::Result<()> {
+    write_ply(traj, None, path)
+}
+
+/// PLY cloud with vertices coloured blue (slow) to
+/// red (fast) by the local speed |f(s)|.
+pub fn export_ply_speed(
+    c: &Coeffs,
+    traj: &[State],
+    path: &Path,
+) -> std::io::Result<()> {
+    let speed: Vec<f64> = traj
+        .iter()
+        .map(|s| {
+            let v = ode::rhs(c, s);
+            (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+        })
+        .collect();
+    write_ply(traj, Some(&speed), path)
+}
+
+fn write_ply(
+    traj: &[State],
+    value: Option<&[f64]>,
+    path: &Path,
+) -> std::io::Result<()> {
+    let f = std::fs::File::create(path)?;
+    let mut w = std::io::BufWriter::new(f);
+    writeln!(w, "ply\nformat ascii 1.0")?;
+    writeln!(w, "element vertex {}", traj.len())?;
+    writeln!(w, "property float x\nproperty float y")?;
+    writeln!(w, "property float z")?;
+    if value.is_some() {
+        writeln!(w, "property uchar red\nproperty uchar green")?;
+        writeln!(w, "property uchar blue")?;
+    }
+    writeln!(w, "end_header")?;
+    let (lo, hi) = value.unwrap_or(&[]).iter().fold(
+        (f64::MAX, f64::MIN),
+        |(a, b), &v| (a.min(v), b.max(v)),
+    );
+    let span = if hi > lo { hi - lo } else { 1.0 };
+    for (i, p) in traj.iter().enumerate() {
+        write!(w, "{} {} {}", p[0], p[1], p[2])?;
+        if let Some(v) = value {
+            let t = (v[i] - lo) / span;
+            let r = (255.0 * t) as u8;
+            write!(w, " {r} 76 {}", 255 - r)?;
+        }
+        writeln!(w)?;
+    }
+    w.flush()
+}
+
+/// Autocorrelation peak needed for a period,
+/// relative to lag 0.
+const PERIOD_PEAK: f64 = 0.5;
+
+/// Fundamental period from the autocorrelation of
+/// the highest-variance coordinate: the first
+/// peak after the first zero crossing, refined
+/// parabolically. None without a clear peak within
+/// half the sampled span.
+pub fn estimate_period(traj: &[State], dt: f64) -> Option<f64> {
+    let n = traj.len();
+    if n < 8 {
+        return None;
+    }
+    let var = |a: usize| {
+        let m = traj.iter().map(|p| p[a]).sum::<f64>() / n as f64;
+        traj.iter().map(|p| (p[a] - m).powi(2)).sum::<f64>()
+    };
+    let axis = (0..3).max_by(|&a, &b| var(a).total_cmp(&var(b)))?;
+    let mean = traj.iter().map(|p| p[axis]).sum::<f64>() / n as f64;
+    let len = (2 * n).next_power_of_two();
+    let mut re = vec![0.0; len];
+    let mut im = vec![0.0; len];
+    for (r, p) in re.iter_mut().zip(traj) {
+        *r = p[axis] - mean;
+    }
+    fft(&mut re, &mut im);
+    for (r, i) in re.iter_mut().zip(im.iter_mut()) {
+        *r = *r * *r + *i * *i;
+        *i = 0.0;
+    }
+    // Forward again: real and even, so no conjugate.
+    fft(&mut re, &mut im);
+    let r0 = re[0];
+    if r0.is_nan() || r0 <= 0.0 {
+        return None;
+    }
+    let ac: Vec<f64> = (0..n / 2)
+        .map(|k| re[k] / r0 * n as f64 / (n - k) as f64)
+        .collect();
+    let zero = ac.iter().position(|&v| v < 0.0)?;
+    let k = (zero.max(1)..ac.len() - 1)
+        .find(|&k| ac[k] >= ac[k - 1] && ac[k] > ac[k + 1])?;
+    if ac[k] < PERIOD_PEAK {
+        return None;
+    }
+    let (a, b, c) = (ac[k - 1], ac[k], ac[k + 1]);
+    let den = a - 2.0 * b + c;
+    let off = if den != 0.0 { 0.5 * (a - c) / den } else { 0.0 };
+    Some((k as f64 + off) * dt)
+}
+
+/// Equal-width histogram over [lo, lo + bins*width].
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    pub lo: f64,
+    pub width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Bin finite values over their range; the
+    /// maximum lands in the last bin.
+    pub fn new(values: &[f64], bins: usize) -> Self {
+        let bins = bins.max(1);
+        let fin = values.iter().copied().filter(|v| v.is_finite());
+        let (lo, hi) = fin.fold((f64::MAX, f64::MIN), |(a, b), v| {
+            (a.min(v), b.max(v))
+        });
+        let (lo, span) = if lo > hi { (0.0, 1.0) } else { (lo, hi - lo) };
+        let width = if span > 0.0 { span / bins as f64 } else { 1.0 };
+        let mut counts = vec![0; bins];
+        for &v in values.iter().filter(|v| v.is_finite()) {
+            let i = ((v - lo) / width) as usize;
+            counts[i.min(bins - 1)] += 1;
+        }
+        Histogram { lo, width, counts }
+    }
+
+    /// (lower edge, upper edge, count) per bin.
+    pub fn bins(&self) -> impl Iterator<Item = (f64, f64, usize)> + '_ {
+        self.counts.iter().enumerate().map(|(i, &n)| {
+            let a = self.lo + i as f64 * self.width;
+            (a, a + self.width, n)
+        })
+    }
+}
+
+/// Relative padding of the box-counting cube.
+const BOX_MARGIN: f64 = 1e-3;
+
+/// Box-counting (capacity) dimension: slope of
+/// log(occupied boxes) against log(1/size) over
+/// box sizes L/2, L/4, ... L/2^levels, L the side
+/// of the padded bounding cube.
+pub fn box_counting_dimension(traj: &[State], levels: usize) -> f64 {
+    if traj.len() < 2 || levels < 2 {
+        return 0.0;
+    }
+    let mut lo = traj[0];
+    let mut hi = traj[0];
+    for p in traj {
+        for a in 0..3 {
+            lo[a] = lo[a].min(p[a]);
+            hi[a] = hi[a].max(p[a]);
+        }
+    }
+    let ext = (0..3).map(|a| hi[a] - lo[a]).fold(0.0f64, f64::max);
+    let side = ext.max(f64::MIN_POSITIVE) * (1.0 + 2.0 * BOX_MARGIN);
+    let lo = lo.map(|v| v - ext * BOX_MARGIN);
+    let pts: Vec<(f64, f64)> = (1..=levels)
+        .map(|k| {
+            let n = (1u64 << k) as f64;
+            let boxes: HashSet<[u64; 3]> = traj
+                .iter()
+                .map(|p| [0, 1, 2].map(|a| ((p[a] - lo[a]) / side * n) as u64))
+                .collect();
+            ((n / side).ln(), (boxes.len() as f64).ln())
+        })
+        .collect();
+    slope(&pts)
+}
+
+/// Visit frequencies of the trajectory over a
+/// res³ grid spanning its bounding box: an
+/// estimate of the invariant measure, summing to 1
+/// (empty for no points or res 0). Index cells
+/// with grid_index.
+pub fn occupancy_grid(traj: &[State], res: usize) -> Vec<f64> {
+    if traj.is_empty() || res == 0 {
+        return Vec::new();
+    }
+    let mut lo = traj[0];
+    let mut hi = traj[0];
+    for p in traj {
+        for a in 0..3 {
+            lo[a] = lo[a].min(p[a]);
+            hi[a] = hi[a].max(p[a]);
+        }
+    }
+    let cell = |p: &State, a: usize| {
+        let w = hi[a] - lo[a];
+        if w > 0.0 {
+            (((p[a] - lo[a]) / w * res as f64) as usize).min(res - 1)
+        } else {
+            0
+        }
+    };
+    let mut grid = vec![0.0; res * res * res];
+    let inc = 1.0 / traj.len() as f64;
+    for p in traj {
+        grid[grid_index(res, cell(p, 0), cell(p, 1), cell(p, 2))] += inc;
+    }
+    grid
+}
+
+/// Flat index of cell (i, j, k) in a res³ grid,
+/// x slowest.
+#[inline]
+pub fn grid_index(res: usize, i: usize, j: usize, k: usize) -> usize {
+    (i * res + j) * res + k
+}
+
+/// Least-squares slope of y on x.
+pub(crate) fn slope(pts: &[(f64, f64)]) -> f64 {
+    let n = pts.len() as f64;
+    let mx = pts.iter().map(|p| p.0).sum::<f64>() / n;
+    let my = pts.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxy: f64 = pts.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+    let sxx: f64 = pts.iter().map(|p| (p.0 - mx) * (p.0 - mx)).sum();
+    sxy / sxx
+}
+
+/// Pick n samples evenly spaced in time.
+pub fn resample_by_time(
+    traj: &[State],
+    n: usize,
+) -> Vec<State> {
+    if traj.len() < 2 || n < 2 || n >= traj.len() {
+        return traj.iter().take(n).copied().collect();
+    }
+    (0..n)
+        .map(|k| traj[k * (traj.len() - 1) / (n - 1)])
+        .collect()
+}
+
+/// Resample to n points equally spaced in
+/// cumulative chord length (linear interpolation).
+pub fn resample_by_arclength(
+    traj: &[State],
+    n: usize,
+) -> Vec<State> {
+    if traj.len() < 2 || n < 2 {
+        return traj.iter().take(n).copied().collect();
+    }
+    let mut cum = Vec::with_capacity(traj.len());
+    cum.push(0.0);
+    for w in traj.windows(2) {
+        let l = cum[cum.len() - 1] + dist(&w[0], &w[1]);
+        cum.push(l);
+    }
+    let total = cum[cum.len() - 1];
+    if total <= 0.0 {
+        return vec![traj[0]; n];
+    }
+    let mut out = Vec::with_capacity(n);
+    let mut seg = 0;
+    for k in 0..n {
+        let t = total * k as f64 / (n - 1) as f64;
+        while seg + 2 < cum.len() && cum[seg + 1] < t {
+            seg += 1;
+        }
+        let len = cum[seg + 1] - cum[seg];
+        let f = if len > 0.0 {
+            ((t - cum[seg]) / len).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (a, b) = (traj[seg], traj[seg + 1]);
+        out.push([
+            a[0] + f * (b[0] - a[0]),
+            a[1] + f * (b[1] - a[1]),
+            a[2] + f * (b[2] - a[2]),
+        ]);
+    }
+    out
+}
+
+/// Keep about `target` of the original points,
+/// one each time the path has covered another
+/// 1/(target - 1) of its length, plus both ends.
+/// Unlike resample_by_arclength, nothing is
+/// interpolated.
+pub fn downsample_arclength(traj: &[State], target: usize) -> Vec<State> {
+    if target < 2 || traj.len() <= target {
+        return traj.to_vec();
+    }
+    let total: f64 = traj.windows(2).map(|w| dist(&w[0], &w[1])).sum();
+    let step = total / (target - 1) as f64;
+    let mut out = vec![traj[0]];
+    let mut run = 0.0;
+    for w in traj[..traj.len() - 1].windows(2) {
+        run += dist(&w[0], &w[1]);
+        if run >= step {
+            out.push(w[1]);
+            run -= step;
+        }
+    }
+    out.push(traj[traj.len() - 1]);
+    out
+}
+
+/// Time window whose bbox trim_transient compares.
+const TRIM_WINDOW: f64 = 20.0;
+/// Relative bbox change still counted as settled.
+const TRIM_TOL: f64 = 0.2;
+/// Slack around the settled bbox, as a fraction of
+/// its diagonal.
+const TRIM_MARGIN: f64 = 0.05;
+
+/// The part of traj (sampled every dt) after it
+/// settles. The attractor's box is that of the
+/// second half; it counts as settled if the last
+/// quarter already spans it (within TRIM_TOL) and
+/// the tail starts after the final excursion beyond
+/// the box. Slow approaches from outside are thus
+/// cut however long they take; a drifting or short
+/// (under 4 TRIM_WINDOW) orbit comes back whole.
+pub fn trim_transient(traj: &[State], dt: f64) -> &[State] {
+    let n = traj.len();
+    let w = (TRIM_WINDOW / dt.max(1e-12)) as usize;
+    if n < 4 * w.max(1) {
+        return traj;
+    }
+    let bbox = |t: &[State]| {
+        let init = [[f64::MAX; 3], [f64::MIN; 3]];
+        t.iter().fold(init, |[lo, hi], p| {
+            let lo = [0, 1, 2].map(|k| lo[k].min(p[k]));
+            [lo, [0, 1, 2].map(|k| hi[k].max(p[k]))]
+        })
+    };
+    let [lo, hi] = bbox(&traj[n / 2..]);
+    let [qlo, qhi] = bbox(&traj[3 * n / 4..]);
+    let diag = dist(&lo, &hi);
+    let drift = dist(&lo, &qlo) + dist(&hi, &qhi);
+    if !diag.is_finite() || drift > TRIM_TOL * diag {
+        return traj;
+    }
+    let m = TRIM_MARGIN * diag;
+    let outside = |p: &State| {
+        (0..3).any(|k| p[k] < lo[k] - m || p[k] > hi[k] + m)
+    };
+    let start = traj.iter().rposition(outside).map_or(0, |i| i + 1);
+    &traj[start..]
+}
+
+/// Fold a trajectory into a fundamental domain of
+/// the given sign-flip symmetries (see
+/// `ode::symmetries`). Each map gets a pivot axis
+/// it flips, not used by an earlier map; points
+/// with a negative pivot coordinate are mapped
+/// back, so the pivot half-space is kept.
+pub fn fold_symmetry(
+    traj: &[State],
+    syms: &[State],
+) -> Vec<State> {
+    let mut used = [false; 3];
+    let mut maps = Vec::new();
+    for s in syms {
+        if let Some(a) = (0..3).find(|&a| s[a] < 0.0 && !used[a])
+        {
+            used[a] = true;
+            maps.push((a, *s));
+        }
+    }
+    traj.iter()
+        .map(|p| {
+            let mut q = *p;
+            for &(a, s) in &maps {
+                if q[a] < 0.0 {
+                    q = [q[0] * s[0], q[1] * s[1], q[2] * s[2]];
+                }
+            }
+            q
+        })
+        .collect()
+}
+
+/// Successive-maxima return map (m_k, m_{k+1}) of a
+/// scalar series.
+pub fn return_map(series: &[f64]) -> Vec<(f64, f64)> {
+    let m: Vec<f64> = series
+        .windows(3)
+        .filter(|w| w[1] > w[0] && w[1] >= w[2])
+        .map(|w| w[1])
+        .collect();
+    m.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+const MAP_BINS: usize = 50;
+const MAP_GRID: usize = 4000;
+const MAP_ITERS: u32 = 8;
+/// Turns smaller than this fraction of the range
+/// are noise, not laps.
+const LAP_TOL: f64 = 0.01;
+/// Fewest map points worth an entropy estimate.
+pub const MIN_MAP_POINTS: usize = 20;
+
+/// Topological entropy of a 1D return map via lap
+/// growth (Misiurewicz-Szlenk): h ≈ ln(laps(fⁿ))/n.
+/// The map is a bin-averaged interpolant of the
+/// samples. None if there are too few samples.
+pub fn topological_entropy(
+    map: &[(f64, f64)],
+) -> Option<f64> {
+    if map.len() < MIN_MAP_POINTS {
+        return None;
+    }
+    let lo = map.iter().map(|p| p.0).fold(f64::MAX, f64::min);
+    let hi = map.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+    if hi <= lo {
+        return Some(0.0);
+    }
+    let w = (hi - lo) / MAP_BINS as f64;
+    let mut acc = [(0.0, 0.0, 0usize); MAP_BINS];
+    for &(x, y) in map {
+        let b = (((x - lo) / w) as usize).min(MAP_BINS - 1);
+        acc[b].0 += x;
+        acc[b].1 += y;
+        acc[b].2 += 1;
+    }
+    let knots: Vec<(f64, f64)> = acc
+        .iter()
+        .filter(|a| a.2 > 0)
+        .map(|a| (a.0 / a.2 as f64, a.1 / a.2 as f64))
+        .collect();
+    let f = |x: f64| interp(&knots, x.clamp(lo, hi));
+    let mut ys: Vec<f64> = (0..MAP_GRID)
+        .map(|i| lo + (hi - lo) * i as f64 / (MAP_GRID - 1) as f64)
+        .collect();
+    for _ in 0..MAP_ITERS {
+        for y in ys.iter_mut() {
+            *y = f(*y);
+        }
+    }
+    let laps = count_laps(&ys, LAP_TOL * (hi - lo));
+    Some((laps as f64).ln() / MAP_ITERS as f64)
+}
+
+/// Lowest return-map entropy across the three
+/// coordinates (noisy, non-1D maps overestimate).
+pub fn entropy_estimate(traj: &[State]) -> Option<f64> {
+    (0..3)
+        .filter_map(|a| {
+            let v: Vec<f64> = traj.iter().map(|p| p[a]).collect();
+            topological_entropy(&return_map(&v))
+        })
+        .reduce(f64::min)
+}
+
+/// Piecewise-linear interpolation through sorted knots.
+fn interp(k: &[(f64, f64)], x: f64) -> f64 {
+    let i = k.partition_point(|p| p.0 < x);
+    if i == 0 {
+        return k[0].1;
+    }
+    if i == k.len() {
+        return k[k.len() - 1].1;
+    }
+    let (a, b) = (k[i - 1], k[i]);
+    if b.0 > a.0 {
+        a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0)
+    } else {
+        a.1
+    }
+}
+
+/// Monotone pieces, ignoring reversals below tol.
+fn count_laps(v: &[f64], tol: f64) -> usize {
+    let mut laps = 1;
+    let mut dir = 0.0;
+    let mut ext = v[0];
+    for &x in &v[1..] {
+        let d = x - ext;
+        if dir == 0.0 {
+            if d.abs() > tol {
+                dir = d.signum();
+                ext = x;
+            }
+        } else if d * dir > 0.0 {
+            ext = x;
+        } else if d.abs() > tol {
+            laps += 1;
+            dir = -dir;
+            ext = x;
+        }
+    }
+    laps
+}
+
+/// Frenet curvature and |torsion| summaries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrenetStats {
+    pub mean_curvature: f64,
+    pub max_curvature: f64,
+    pub mean_torsion: f64,
+    pub max_torsion: f64,
+}
+
+/// Below this |v×a|², curvature and torsion are 0.
+const FRENET_EPS: f64 = 1e-18;
+
+/// Curvature and torsion of the flow at s, using
+/// v = f, a = J v, jerk = J a + H(v, v) (exact for
+/// quadratic f, whose Hessian H is constant).
+pub fn curvature_torsion(c: &Coeffs, s: &State) -> (f64, f64) {
+    let v = ode::rhs(c, s);
+    let jm = ode::jacobian(c, s);
+    let a = mat_vec(&jm, &v);
+    let mut j = mat_vec(&jm, &a);
+    for (eq, ji) in j.iter_mut().enumerate() {
+        let k = &c[eq * NTERMS..];
+        *ji += 2.0
+            * (k[4] * v[0] * v[0]
+                + k[5] * v[1] * v[1]
+                + k[6] * v[2] * v[2]
+                + k[7] * v[0] * v[1]
+                + k[8] * v[0] * v[2]
+                + k[9] * v[1] * v[2]);
+    }
+    let x = cross(&v, &a);
+    let x2 = x[0] * x[0] + x[1] * x[1] + x[2] * x[2];
+    if x2 < FRENET_EPS {
+        return (0.0, 0.0);
+    }
+    let sp = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let kappa = x2.sqrt() / (sp * sp * sp);
+    let tau = (x[0] * j[0] + x[1] * j[1] + x[2] * j[2]) / x2;
+    (kappa, tau)
+}
+
+/// Curvature/torsion statistics along a trajectory.
+pub fn frenet_stats(c: &Coeffs, traj: &[State]) -> FrenetStats {
+    let mut st = FrenetStats {
+        mean_curvature: 0.0,
+        max_curvature: 0.0,
+        mean_torsion: 0.0,
+        max_torsion: 0.0,
+    };
+    let n = traj.len().max(1) as f64;
+    for s in traj {
+        let (k, t) = curvature_torsion(c, s);
+        let t = t.abs();
+        st.mean_curvature += k / n;
+        st.mean_torsion += t / n;
+        st.max_curvature = st.max_curvature.max(k);
+        st.max_torsion = st.max_torsion.max(t);
+    }
+    st
+}
+
+/// Rigid map p -> rot · (p - center); rows of rot
+/// are the principal axes, largest variance first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transform {
+    pub center: State,
+    pub rot: [[f64; 3]; 3],
+}
+
+impl Transform {
+    pub fn apply(&self, p: &State) -> State {
+        let c = self.center;
+        mat_vec(&self.rot, &[p[0] - c[0], p[1] - c[1], p[2] - c[2]])
+    }
+
+    pub fn invert(&self, q: &State) -> State {
+        let r = &self.rot;
+        let mut p = self.center;
+        for i in 0..3 {
+            for (j, pj) in p.iter_mut().enumerate() {
+                *pj += r[i][j] * q[i];
+            }
+        }
+        p
+    }
+}
+
+/// Centre on the centroid and rotate so the
+/// principal axes (PCA) align with x, y, z.
+/// Axis signs make the third moment along x and y
+/// positive; z = x × y keeps it a proper rotation.
+pub fn canonical_pose(traj: &[State]) -> (Vec<State>, Transform) {
+    let n = traj.len().max(1) as f64;
+    let mut c = [0.0; 3];
+    for p in traj {
+        for (ci, pi) in c.iter_mut().zip(p) {
+            *ci += pi / n;
+        }
+    }
+    let mut cov = [[0.0; 3]; 3];
+    for p in traj {
+        let d = [p[0] - c[0], p[1] - c[1], p[2] - c[2]];
+        for i in 0..3 {
+            for j in 0..3 {
+                cov[i][j] += d[i] * d[j] / n;
+            }
+        }
+    }
+    let (val, vec) = sym_eig3(cov);
+    let mut idx = [0, 1, 2];
+    idx.sort_by(|&a, &b| val[b].total_cmp(&val[a]));
+    let mut rot = idx.map(|k| [vec[0][k], vec[1][k], vec[2][k]]);
+    for axis in rot.iter_mut().take(2) {
+        let skew: f64 = traj
+            .iter()
+            .map(|p| {
+                let t = (p[0] - c[0]) * axis[0]
+                    + (p[1] - c[1]) * axis[1]
+                    + (p[2] - c[2]) * axis[2];
+                t * t * t
+            })
+            .sum();
+        if skew < 0.0 {
+            *axis = axis.map(|v| -v);
+        }
+    }
+    rot[2] = cross(&rot[0], &rot[1]);
+    let tf = Transform { center: c, rot };
+    (traj.iter().map(|p| tf.apply(p)).collect(), tf)
+}
+
+/// Jacobi eigen-decomposition of a symmetric 3x3.
+/// Returns eigenvalues and eigenvectors (columns).
+fn sym_eig3(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..50 {
+        let off = a[0][1].abs() + a[0][2].abs() + a[1][2].abs();
+        if off < 1e-14 {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q].abs() < 1e-300 {
+                continue;
+            }
+            let th = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = th.signum() / (th.abs() + (th * th + 1.0).sqrt());
+            let t = if th == 0.0 { 1.0 } else { t };
+            let cs = 1.0 / (t * t + 1.0).sqrt();
+            let sn = t * cs;
+            let turn = |row: &mut [f64; 3]| {
+                let (x, y) = (row[p], row[q]);
+                row[p] = cs * x - sn * y;
+                row[q] = sn * x + cs * y;
+            };
+            a.iter_mut().for_each(turn);
+            let (rp, rq) = (a[p], a[q]);
+            a[p] = [0, 1, 2].map(|k| cs * rp[k] - sn * rq[k]);
+            a[q] = [0, 1, 2].map(|k| sn * rp[k] + cs * rq[k]);
+            v.iter_mut().for_each(turn);
+        }
+    }
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+#[inline]
+fn mat_vec(m: &[[f64; 3]; 3], v: &State) -> State {
+    m.map(|r| r[0] * v[0] + r[1] * v[1] + r[2] * v[2])
+}
+
+/// (mean, max - min) of f along traj; zeros if
+/// empty. For a quantity the flow should conserve,
+/// the drift measures integration error.
+pub fn track_quantity(
+    traj: &[State],
+    f: impl Fn(&State) -> f64,
+) -> (f64, f64) {
+    if traj.is_empty() {
+        return (0.0, 0.0);
+    }
+    let (mut sum, mut lo, mut hi) = (0.0, f64::INFINITY, f64::NEG_INFINITY);
+    for p in traj {
+        let v = f(p);
+        sum += v;
+        lo = lo.min(v);
+        hi = hi.max(v);
+    }
+    (sum / traj.len() as f64, hi - lo)
+}
+
+/// Separation, as a fraction of the reference
+/// orbit's bbox diagonal so far, at which
+/// divergence_series renormalizes.
+const DIVERGE_SAT: f64 = 0.1;
+
+/// ln of the separation between the orbit from s0
+/// and one started eps away along x, after each of
+/// n RK4 steps. When the gap nears the attractor
+/// size (DIVERGE_SAT) it is shrunk back to eps
+/// along the same direction and the lost growth
+/// carried over, so the series keeps rising at λ1
+/// instead of flattening out. Stops early if either
+/// orbit blows up.
+pub fn divergence_series(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    n: usize,
+    eps: f64,
+) -> Vec<f64> {
+    let (mut a, mut b) = (*s0, [s0[0] + eps, s0[1], s0[2]]);
+    let (mut lo, mut hi) = (*s0, *s0);
+    let mut carried = 0.0;
+    let mut out = Vec::with_capacity(n);
+    for _ in 0..n {
+        a = ode::rk4_step(c, &a, dt);
+        b = ode::rk4_step(c, &b, dt);
+        let d = dist(&a, &b);
+        if !d.is_finite() || !a.iter().all(|v| v.is_finite()) {
+            break;
+        }
+        for k in 0..3 {
+            lo[k] = lo[k].min(a[k]);
+            hi[k] = hi[k].max(a[k]);
+        }
+        out.push(carried + d.max(f64::MIN_POSITIVE).ln());
+        if d > DIVERGE_SAT * dist(&lo, &hi) {
+            let f = eps / d;
+            b = [0, 1, 2].map(|k| a[k] + f * (b[k] - a[k]));
+            carried += (d / eps).ln();
+        }
+    }
+    out
+}
+
+/// Time for a separation of `initial_sep` to grow
+/// to `tolerance` at rate λ1: T_λ ln(tol/sep). None
+/// without a Lyapunov time or for bad bounds.
+pub fn predictability_horizon(
+    data: &LyapData,
+    initial_sep: f64,
+    tolerance: f64,
+) -> Option<f64> {
+    let t = data.lyapunov_time?;
+    (initial_sep > 0.0 && tolerance >= initial_sep)
+        .then(|| t * (tolerance / initial_sep).ln())
+}
+
+/// Newton iterations per start.
+const NEWTON_ITERS: usize = 50;
+/// |f| below which Newton has converged.
+const NEWTON_TOL: f64 = 1e-10;
+/// Roots closer than this are the same point.
+const ROOT_MERGE: f64 = 1e-6;
+
+/// Equilibria of c: Newton's method on rhs = 0 from
+/// each start, keeping distinct converged roots in
+/// order of discovery.
+pub fn fixed_points(c: &Coeffs, starts: &[State]) -> Vec<State> {
+    let mut roots: Vec<State> = Vec::new();
+    for s0 in starts {
+        let Some(p) = newton(c, s0) else { continue };
+        if roots.iter().all(|q| dist(&p, q) > ROOT_MERGE) {
+            roots.push(p);
+        }
+    }
+    roots
+}
+
+/// Newton from s; None if it stalls, hits a
+/// singular Jacobian or runs off.
+fn newton(c: &Coeffs, s: &State) -> Option<State> {
+    let mut s = *s;
+    for _ in 0..NEWTON_ITERS {
+        let f = ode::rhs(c, &s);
+        if f.iter().map(|v| v * v).sum::<f64>().sqrt() < NEWTON_TOL {
+            return Some(s);
+        }
+        let d = solve3(&ode::jacobian(c, &s), &f)?;
+        s = [0, 1, 2].map(|k| s[k] - d[k]);
+        if !s.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+    }
+    None
+}
+
+/// Solve m x = b by Cramer's rule; None if m is
+/// singular.
+fn solve3(m: &[[f64; 3]; 3], b: &State) -> Option<State> {
+    let d = ode::det3(m);
+    if d.abs() < 1e-300 || !d.is_finite() {
+        return None;
+    }
+    Some([0, 1, 2].map(|col| {
+        let mut a = *m;
+        for (row, v) in a.iter_mut().zip(b) {
+            row[col] = *v;
+        }
+        ode::det3(&a) / d
+    }))
+}
+
+/// Jacobian eigenvalues of c at p with positive
+/// real part: 0 is a stable point, 3 a source,
+/// anything else a saddle.
+pub fn unstable_directions(c: &Coeffs, p: &State) -> usize {
+    let ev = ode::eig3(&ode::jacobian(c, p));
+    ev.iter().filter(|z| z.re > 0.0).count()
+}
+
+/// Weights of the attractor_distance terms.
+const W_SPECTRUM: f64 = 1.0;
+const W_DIM: f64 = 1.0;
+const W_SHAPE: f64 = 1.0;
+
+/// Behavioural distance between two catalogued
+/// attractors, ~0 for the same attractor and O(1)
+/// for different ones. Sum of:
+/// - W_SPECTRUM × |Δspectrum| / (|a| + |b|), the
+///   relative spectrum difference in [0, 1];
+/// - W_DIM × |Δky_dim|;
+/// - W_SHAPE × |Δshape|, shape being the bbox sides
+///   sorted and divided by the longest, so axis
+///   order and overall scale don't count.
+pub fn attractor_distance(a: &Entry, b: &Entry) -> f64 {
+    let norm = |v: &[f64; 3]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let ds: [f64; 3] = [0, 1, 2].map(|k| a.spectrum[k] - b.spectrum[k]);
+    let scale = norm(&a.spectrum) + norm(&b.spectrum);
+    let spec = if scale > 0.0 { norm(&ds) / scale } else { 0.0 };
+    let (sa, sb) = (shape(&a.bbox), shape(&b.bbox));
+    W_SPECTRUM * spec
+        + W_DIM * (a.ky_dim - b.ky_dim).abs()
+        + W_SHAPE * dist(&sa, &sb)
+}
+
+/// Bbox sides, longest first, over the longest.
+fn shape(bbox: &[[f64; 3]; 2]) -> State {
+    let mut e = [0, 1, 2].map(|k| bbox[1][k] - bbox[0][k]);
+    e.sort_by(|x, y| y.total_cmp(x));
+    if e[0] > 0.0 {
+        e.map(|v| v / e[0])
+    } else {
+        [0.0; 3]
+    }
+}
+
+#[inline]
+fn dist(a: &State, b: &State) -> f64 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sine_psd_peak() {
+        let (dt, f0) = (0.01, 2.0);
+        let s: Vec<f64> = (0..1000)
+            .map(|i| (2.0 * PI * f0 * i as f64 * dt).sin())
+            .collect();
+        let psd = power_spectrum(&s, dt);
+        let peak = psd
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        let df = psd[1].0;
+        assert!(
+            (peak.0 - f0).abs() <= df,
+            "peak at {}, expected {f0}", peak.0
+        );
+    }
+
+    #[test]
+    fn chaos_is_broadband() {
+        // Share of power in the 5 strongest bins.
+        let top = |s: &[f64]| {
+            let mut p: Vec<f64> =
+                power_spectrum(s, 0.01).iter().map(|v| v.1).collect();
+            let total: f64 = p.iter().sum();
+            p.sort_by(|a, b| b.total_cmp(a));
+            p[..5].iter().sum::<f64>() / total
+        };
+        let sine: Vec<f64> =
+            (0..4096).map(|i| (0.37 * i as f64).sin()).collect();
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = ode::integrate(&c, &[1.0, 1.0, 1.0], 0.01, 2000);
+        let x: Vec<f64> = ode::integrate_traj(&c, &s0, 0.01, 4095)
+            .iter()
+            .map(|p| p[0])
+            .collect();
+        let (a, b) = (top(&sine), top(&x));
+        assert!(a > 0.9 && b < 0.5, "sine {a}, lorenz {b}");
+    }
+
+    #[test]
+    fn downsample_keeps_ends() {
+        let traj = ode::integrate_traj(
+            &ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0),
+            &[0.1; 3],
+            0.01,
+            20000,
+        );
+        let d = downsample_arclength(&traj, 500);
+        let n = d.len() as f64;
+        assert!((n - 500.0).abs() <= 0.02 * 500.0, "{n}");
+        assert_eq!(d[0], traj[0]);
+        assert_eq!(d[d.len() - 1], traj[traj.len() - 1]);
+        assert!(d.iter().all(|p| traj.contains(p)));
+        assert_eq!(downsample_arclength(&traj[..10], 500).len(), 10);
+    }
+
+    #[test]
+    fn trim_cuts_approach_only() {
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = [40.0, -40.0, 120.0];
+        let far = ode::integrate_traj(&c, &s0, 0.01, 20000);
+        let t = trim_transient(&far, 0.01);
+        assert!(t.len() < far.len() && t.len() > far.len() / 2);
+        assert!(t[0][2] < 60.0, "{:?}", t[0]);
+        // Already on the attractor: nothing to cut.
+        let on = ode::integrate_traj(&c, &far[10000], 0.01, 20000);
+        assert!(trim_transient(&on, 0.01).len() > on.len() * 99 / 100);
+        // A steady drift never settles.
+        let line: Vec<State> =
+            (0..20000).map(|i| [i as f64 * 0.01, 0.0, 0.0]).collect();
+        assert_eq!(trim_transient(&line, 0.01).len(), line.len());
+    }
+
+    #[test]
+    fn divergence_grows_at_lambda1() {
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = ode::integrate(&c, &[0.1; 3], 0.01, 2000);
+        let eps = 1e-8;
+        let v = divergence_series(&c, &s0, 0.01, 20000, eps);
+        assert_eq!(v.len(), 20000);
+        assert!((v[0] - eps.ln()).abs() < 1.0, "{}", v[0]);
+        let pts: Vec<(f64, f64)> = v
+            .iter()
+            .enumerate()
+            .map(|(i, &l)| (i as f64 * 0.01, l))
+            .collect();
+        let l1 = slope(&pts);
+        assert!((0.7..1.1).contains(&l1), "λ1 {l1}");
+        // Converging to a stable point: shrinking gap.
+        let c = ode::lorenz_coeffs(10.0, 10.0, 8.0 / 3.0);
+        let v = divergence_series(&c, &[1.0; 3], 0.01, 2000, eps);
+        assert!(v[v.len() - 1] < v[0] - 5.0);
+    }
+
+    #[test]
+    fn energy_drift_flags_dissipation() {
+        // x' = y, y' = -x conserves x² + y².
+        let mut c = [0.0; ode::NCOEFFS];
+        c[2] = 1.0;
+        c[NTERMS + 1] = -1.0;
+        let energy = |p: &State| p[0] * p[0] + p[1] * p[1];
+        let (dt, n) = (0.5, 2000);
+        let mut s = [1.0, 0.0, 0.0];
+        let (mut rk, mut mid) = (vec![s], vec![s]);
+        for _ in 0..n {
+            s = ode::rk4_step(&c, &s, dt);
+            rk.push(s);
+        }
+        s = [1.0, 0.0, 0.0];
+        for _ in 0..n {
+            s = ode::midpoint_step(&c, &s, dt);
+            mid.push(s);
+        }
+        let (m, drift) = track_quantity(&mid, energy);
+        assert!((m - 1.0).abs() < 1e-9 && drift < 1e-9, "{m} {drift}");
+        let (_, drift) = track_quantity(&rk, energy);
+        assert!(drift > 0.1, "RK4 drift {drift}");
+        assert_eq!(track_quantity(&[], energy), (0.0, 0.0));
+    }
+
+    #[test]
+    fn arclength_equidistant() {
+        // Quarter circle, angle = t², so points
+        // bunch up near t = 0.
+        let traj: Vec<State> = (0..2000)
+            .map(|i| {
+                let t = i as f64 / 1999.0;
+                let a = 0.5 * PI * t * t;
+                [a.cos(), a.sin(), 0.0]
+            })
+            .collect();
+        let r = resample_by_arclength(&traj, 50);
+        assert_eq!(r.len(), 50);
+        let d: Vec<f64> = r
+            .windows(2)
+            .map(|w| dist(&w[0], &w[1]))
+            .collect();
+        let mean = d.iter().sum::<f64>() / d.len() as f64;
+        for v in d {
+            assert!((v - mean).abs() < 0.01 * mean);
+        }
+    }
+
+    #[test]
+    fn fold_lorenz_half_space() {
+        use crate::ode::{integrate_traj, lorenz_coeffs, symmetries};
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let traj = integrate_traj(&c, &[1.0, 1.0, 1.0], 0.01, 5000);
+        assert!(traj.iter().any(|p| p[0] < 0.0));
+        let f = fold_symmetry(&traj, &symmetries(&c, 0.0));
+        assert!(f.iter().all(|p| p[0] >= 0.0));
+    }
+
+    #[test]
+    fn entropy_lorenz_vs_monotone() {
+        use crate::ode::{integrate_traj, lorenz_coeffs};
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let traj = integrate_traj(&c, &[1.0, 1.0, 1.0], 0.01, 50000);
+        let z: Vec<f64> = traj[1000..].iter().map(|p| p[2]).collect();
+        let h = topological_entropy(&return_map(&z)).unwrap();
+        assert!(h > 0.3, "Lorenz entropy {h}");
+        let mono: Vec<(f64, f64)> = (0..100)
+            .map(|i| {
+                let x = i as f64 / 99.0;
+                (x, 0.5 * x + 0.2)
+            })
+            .collect();
+        let h = topological_entropy(&mono).unwrap();
+        assert!(h.abs() < 1e-9, "monotone entropy {h}");
+    }
+
+    #[test]
+    fn helix_curvature_torsion() {
+        // x' = -y, y' = x, z' = h: helix of radius 1.
+        let h = 0.5;
+        let mut c = [0.0; crate::ode::NCOEFFS];
+        c[2] = -1.0;
+        c[NTERMS + 1] = 1.0;
+        c[2 * NTERMS] = h;
+        let traj = crate::ode::integrate_traj(
+            &c, &[1.0, 0.0, 0.0], 0.01, 1000,
+        );
+        let st = frenet_stats(&c, &traj);
+        let d = 1.0 + h * h;
+        assert!((st.mean_curvature - 1.0 / d).abs() < 1e-6);
+        assert!((st.mean_torsion - h / d).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pose_invariant_to_rotation() {
+        let mut c = [0.0; crate::ode::NCOEFFS];
+        c[2] = -1.0;
+        c[3] = -1.0;
+        c[NTERMS + 1] = 1.0;
+        c[NTERMS + 2] = 0.2;
+        c[2 * NTERMS] = 0.2;
+        c[2 * NTERMS + 3] = -5.7;
+        c[2 * NTERMS + 8] = 1.0;
+        let traj = crate::ode::integrate_traj(
+            &c, &[0.1, 0.1, 0.1], 0.01, 20000,
+        );
+        let traj = &traj[5000..];
+        let (a, b) = (0.7f64, -1.1f64);
+        let rot = |p: &State| -> State {
+            let (x, y) = (a.cos() * p[0] - a.sin() * p[1],
+                a.sin() * p[0] + a.cos() * p[1]);
+            let (y, z) = (b.cos() * y - b.sin() * p[2],
+                b.sin() * y + b.cos() * p[2]);
+            [x + 3.0, y - 1.0, z + 2.0]
+        };
+        let moved: Vec<State> = traj.iter().map(rot).collect();
+        let (p0, tf) = canonical_pose(traj);
+        let (p1, _) = canonical_pose(&moved);
+        for (u, v) in p0.iter().zip(&p1) {
+            assert!(dist(u, v) < 1e-6, "{u:?} vs {v:?}");
+        }
+        let back = tf.invert(&p0[0]);
+        assert!(dist(&back, &traj[0]) < 1e-9);
+    }
+
+    #[test]
+    fn histogram_bins_catalog_metrics() {
+        use crate::catalog::Entry;
+        let c = [0.0; crate::ode::NCOEFFS];
+        let es: Vec<Entry> = [(0.1, 2.0), (0.15, 2.05), (0.5, 2.9)]
+            .iter()
+            .map(|&(l, d)| Entry::new(&c, [l, 0.0, -1.0], d, &[], "t"))
+            .collect();
+        let l1: Vec<f64> = es.iter().map(|e| e.spectrum[0]).collect();
+        let h = Histogram::new(&l1, 4);
+        assert_eq!(h.counts, vec![2, 0, 0, 1]);
+        assert!((h.lo - 0.1).abs() < 1e-12);
+        assert!((h.width - 0.1).abs() < 1e-12);
+        let ky: Vec<f64> = es.iter().map(|e| e.ky_dim).collect();
+        assert_eq!(Histogram::new(&ky, 2).counts, vec![2, 1]);
+        assert_eq!(Histogram::new(&[1.0, 1.0], 3).counts, vec![2, 0, 0]);
+    }
+
+    #[test]
+    fn box_dimension_of_plane() {
+        // 300x300 grid on a tilted plane.
+        let traj: Vec<State> = (0..90000)
+            .map(|i| {
+                let (u, v) = ((i % 300) as f64, (i / 300) as f64);
+                [u / 300.0, v / 300.0, 0.5 * u / 300.0]
+            })
+            .collect();
+        let d = box_counting_dimension(&traj, 6);
+        assert!((d - 2.0).abs() < 0.1, "plane dim={d}");
+        let line: Vec<State> =
+            (0..10000).map(|i| [i as f64, 0.0, 0.0]).collect();
+        let d = box_counting_dimension(&line, 8);
+        assert!((d - 1.0).abs() < 0.05, "line dim={d}");
+    }
+
+    #[test]
+    fn embedding_and_mi_delay() {
+        let s: Vec<f64> = (0..6).map(|i| i as f64).collect();
+        let e = delay_embed(&s, 3, 2);
+        assert_eq!(e, vec![vec![0.0, 2.0, 4.0], vec![1.0, 3.0, 5.0]]);
+        assert!(delay_embed(&s, 4, 2).is_empty());
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = ode::integrate(&c, &[1.0, 1.0, 1.0], 0.01, 2000);
+        let x: Vec<f64> = ode::integrate_traj(&c, &s0, 0.01, 20000)
+            .iter()
+            .map(|p| p[0])
+            .collect();
+        // First minimum near 0.2 time units for x.
+        let tau = mutual_information_tau(&x, 60);
+        assert!((15..=25).contains(&tau), "tau={tau}");
+    }
+
+    #[test]
+    fn rqa_periodic_vs_noise() {
+        let circle: Vec<State> = (0..3000)
+            .map(|i| {
+                let a = 0.05 * i as f64;
+                [a.cos(), a.sin(), 0.0]
+            })
+            .collect();
+        // Deterministic scatter from an LCG.
+        let mut x = 12345u64;
+        let mut next = || {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (x >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let noise: Vec<State> =
+            (0..1000).map(|_| [next(), next(), next()]).collect();
+        let rr = recurrence_rate(&circle, 0.1);
+        assert!(rr > 0.0 && rr < recurrence_rate(&circle, 0.3));
+        assert!(recurrence_determinism(&circle, 0.1) > 0.95);
+        assert!(recurrence_determinism(&noise, 0.1) < 0.2);
+        assert_eq!(recurrence_rate(&circle[..1], 0.1), 0.0);
+    }
+
+    #[test]
+    fn basin_splits_bistable() {
+        // x' = x - x², y' = -y, z' = -z: x > 0 settles
+        // at x = 1, x < 0 blows up.
+        let mut c = [0.0; 30];
+        c[1] = 1.0;
+        c[4] = -1.0;
+        c[12] = -1.0;
+        c[23] = -1.0;
+        let scan = basin_scan(&c, 2, 0.5);
+        assert_eq!(scan.len(), 8);
+        for (s0, d) in &scan {
+            if s0[0] > 0.0 {
+                assert!(matches!(d, Dynamics::FixedPoint), "{d:?}");
+            } else {
+                assert!(matches!(d, Dynamics::Divergent { .. }), "{d:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn ply_header_and_colours() {
+        let path = std::env::temp_dir().join("attractor_test.ply");
+        // dx/dt = x: speed grows with |x|.
+        let mut c = [0.0; 30];
+        c[1] = 1.0;
+        let traj = [[1.0, 0.0, 0.0], [3.0, 0.0, 0.0], [2.0, 0.0, 0.0]];
+        export_ply_speed(&c, &traj, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let (head, body) = text.split_once("end_header\n").unwrap();
+        assert!(head.starts_with("ply\nformat ascii 1.0\n"));
+        assert!(head.contains("element vertex 3\n"));
+        assert!(head.contains("property uchar red\n"));
+        let rows: Vec<&str> = body.lines().collect();
+        let want = ["1 0 0 0 76 255", "3 0 0 255 76 0", "2 0 0 127 76 128"];
+        assert_eq!(rows, want);
+        export_ply(&traj, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("red"));
+        assert!(text.ends_with("end_header\n1 0 0\n3 0 0\n2 0 0\n"));
+    }
+
+    #[test]
+    fn period_of_sine_orbit() {
+        let circle = |period: f64| -> Vec<State> {
+            (0..5000)
+                .map(|i| {
+                    let t = 2.0 * PI * i as f64 * 0.01 / period;
+                    [t.cos(), 0.5 * t.sin(), 0.0]
+                })
+                .collect()
+        };
+        let p = estimate_period(&circle(2.5), 0.01).unwrap();
+        assert!((p - 2.5).abs() < 0.01, "period={p}");
+        // Longer than half the 50-unit span.
+        assert_eq!(estimate_period(&circle(40.0), 0.01), None);
+        assert_eq!(estimate_period(&[[1.0; 3]; 100], 0.01), None);
+    }
+
+    #[test]
+    fn distance_separates_attractors() {
+        use crate::lyapunov::full_spectrum;
+        use crate::presets::{lorenz_coeffs, rossler_coeffs};
+        let run = |c: &Coeffs, s0: &State| {
+            let d = full_spectrum(c, s0, 0.005, 2000, 40000, 10).unwrap();
+            let s = ode::integrate(c, s0, 0.01, 2000);
+            let traj = ode::integrate_traj(c, &s, 0.01, 20000);
+            Entry::new(c, d.spectrum, d.ky_dim, &traj, "t")
+        };
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let a = run(&lor, &[1.0, 1.0, 1.0]);
+        let b = run(&lor, &[-3.0, 2.0, 20.0]);
+        let ros = run(&rossler_coeffs(0.2, 0.2, 5.7), &[0.1; 3]);
+        let same = attractor_distance(&a, &b);
+        let diff = attractor_distance(&a, &ros);
+        assert!(same < 0.1, "same={same}");
+        assert!(diff > 0.5, "diff={diff}");
+        assert_eq!(attractor_distance(&a, &a), 0.0);
+    }
+
+    #[test]
+    fn occupancy_is_normalized() {
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let traj = ode::integrate_traj(&c, &[1.0; 3], 0.01, 5000);
+        let g = occupancy_grid(&traj, 8);
+        assert_eq!(g.len(), 512);
+        assert!((g.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        // A segment from 0 to 1 along x fills the
+        // i axis evenly.
+        let line: Vec<State> =
+            (0..400).map(|i| [i as f64 / 399.0, 0.0, 0.0]).collect();
+        let g = occupancy_grid(&line, 4);
+        for i in 0..4 {
+            assert!((g[grid_index(4, i, 0, 0)] - 0.25).abs() < 0.01);
+        }
+        assert!(occupancy_grid(&[], 4).is_empty());
+    }
+
+    #[test]
+    fn lorenz_horizon() {
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let d = crate::lyapunov::full_spectrum(
+            &c, &[1.0; 3], 0.005, 1000, 20000, 10,
+        )
+        .unwrap();
+        let t = d.lyapunov_time.unwrap();
+        assert!((1.0..1.3).contains(&t), "{t}");
+        // e^(λ1 T) = 1e6 at T = T_λ ln 1e6.
+        let h = predictability_horizon(&d, 1e-8, 1e-2).unwrap();
+        assert!((h - t * 1e6f64.ln()).abs() < 1e-9);
+        assert_eq!(predictability_horizon(&d, 1.0, 0.1), None);
+    }
+
+    #[test]
+    fn lorenz_fixed_points() {
+        let (b, rho) = (8.0 / 3.0, 28.0);
+        let c = ode::lorenz_coeffs(10.0, rho, b);
+        let starts = [[0.1; 3], [5.0, 5.0, 20.0], [-5.0, -5.0, 20.0]];
+        let mut fp = fixed_points(&c, &starts);
+        // Repeated starts add nothing.
+        assert_eq!(fixed_points(&c, &[[0.1; 3], [0.2; 3]]).len(), 1);
+        fp.sort_by(|p, q| p[0].total_cmp(&q[0]));
+        let r = (b * (rho - 1.0)).sqrt();
+        let want = [[-r, -r, rho - 1.0], [0.0; 3], [r, r, rho - 1.0]];
+        assert_eq!(fp.len(), 3, "{fp:?}");
+        for (p, w) in fp.iter().zip(&want) {
+            assert!(dist(p, w) < 1e-8, "{p:?} vs {w:?}");
+        }
+        // Origin: one unstable direction; C±: the
+        // weakly unstable complex pair at rho > 24.74.
+        let u: Vec<usize> =
+            fp.iter().map(|p| unstable_directions(&c, p)).collect();
+        assert_eq!(u, [2, 1, 2]);
+        let stable = ode::lorenz_coeffs(10.0, 10.0, b);
+        let fp = fixed_points(&stable, &[[5.0, 5.0, 9.0]]);
+        assert_eq!(unstable_directions(&stable, &fp[0]), 0);
+    }
+}
@@ -1,117 +1,1370 @@
-// Save/load discovered attractors.
-
-use crate::ode::{Coeffs, State};
-use serde::{Deserialize, Serialize};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::path::Path;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Entry {
-    pub id: u64,
-    pub coeffs: Vec<f64>,
-    pub spectrum: [f64; 3],
-    pub ky_dim: f64,
-    pub trajectory: Vec<[f64; 3]>,
-    #[serde(default)]
-    pub method: String,
-}
-
-impl Entry {
-    pub fn new(
-        coeffs: &Coeffs,
-        spectrum: [f64; 3],
-        ky_dim: f64,
-        traj: &[State],
-        method: &str,
-    ) -> Self {
-        let id = coeff_hash(coeffs);
-        Entry {
-            id,
-            coeffs: coeffs.to_vec(),
-            spectrum,
-            ky_dim,
-            trajectory: traj.to_vec(),
-            method: method.to_string(),
-        }
-    }
-}
-
-/// Hash coefficients for quick ID.
-pub fn coeff_hash(c: &Coeffs) -> u64 {
-    let mut h = DefaultHasher::new();
-    for &v in c.iter() {
-        v.to_bits().hash(&mut h);
-    }
-    h.finish()
-}
-
-/// Save entry to JSON file in dir.
-pub fn save(
-    dir: &Path,
-    entry: &Entry,
-) -> std::io::Result<()> {
-    std::fs::create_dir_all(dir)?;
-    let path = dir.join(
-        format!("{:016x}.json", entry.id),
-    );
-    let json = serde_json::to_string_pretty(entry)
-        .map_err(|e| {
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                e,
-            )
-        })?;
-    std::fs::write(path, json)
-}
-
-/// Load all entries from dir.
-pub fn load_all(
-    dir: &Path,
-) -> std::io::Result<Vec<Entry>> {
-    let mut entries = Vec::new();
-    if !dir.exists() {
-        return Ok(entries);
-    }
-    for item in std::fs::read_dir(dir)? {
-        let item = item?;
-        let p = item.path();
-        if p.extension().map_or(false, |e| e == "json")
-        {
-            let data = std::fs::read_to_string(&p)?;
-            if let Ok(e) =
-                serde_json::from_str::<Entry>(&data)
-            {
-                entries.push(e);
-            }
-        }
-    }
-    Ok(entries)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ode::NCOEFFS;
-    use std::path::PathBuf;
-
-    #[test]
-    fn roundtrip() {
-        let c = [0.5; NCOEFFS];
-        let traj = vec![[1.0, 2.0, 3.0]; 5];
-        let entry = Entry::new(
-            &c,
-            [0.9, 0.0, -14.0],
-            2.06,
-            &traj,
-            "test",
-        );
-        let dir = PathBuf::from("/tmp/attractor_test");
-        let _ = std::fs::remove_dir_all(&dir);
-        save(&dir, &entry).unwrap();
-        let loaded = load_all(&dir).unwrap();
-        assert_eq!(loaded.len(), 1);
-        assert_eq!(loaded[0].id, entry.id);
-        let _ = std::fs::remove_dir_all(&dir);
-    }
-}
+// Save/load discovered attractors.
+
+use crate::analysis::{FrenetStats, Histogram, Transform};
+use crate::lyapunov;
+use crate::ode::{self, Coeffs, State};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub id: u64,
+    pub coeffs: Vec<f64>,
+    pub spectrum: [f64; 3],
+    pub ky_dim: f64,
+    pub trajectory: Vec<[f64; 3]>,
+    #[serde(default)]
+    pub method: String,
+    /// Time rescaling applied to coeffs (c/k).
+    #[serde(default = "unit")]
+    pub time_scale: f64,
+    /// Return-map topological entropy, if 1D-like.
+    #[serde(default)]
+    pub topo_entropy: Option<f64>,
+    #[serde(default)]
+    pub frenet: Option<FrenetStats>,
+    #[serde(default)]
+    pub unstable_dim: usize,
+    /// Canonical pose of the trajectory (PCA frame).
+    #[serde(default)]
+    pub pose: Option<Transform>,
+    /// RNG seed of the search run that found it.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Trajectory [min, max] per coordinate.
+    #[serde(default)]
+    pub bbox: [[f64; 3]; 2],
+    /// Trajectory mean.
+    #[serde(default)]
+    pub centroid: [f64; 3],
+    /// Nonzero coefficients.
+    #[serde(default)]
+    pub nonzero: usize,
+    /// Trajectory thinned by arc length, so its
+    /// points are not TRAJ_DT apart in time.
+    #[serde(default)]
+    pub resampled: bool,
+}
+
+fn unit() -> f64 {
+    1.0
+}
+
+impl Entry {
+    pub fn new(
+        coeffs: &Coeffs,
+        spectrum: [f64; 3],
+        ky_dim: f64,
+        traj: &[State],
+        method: &str,
+    ) -> Self {
+        let id = coeff_hash(coeffs);
+        let (bbox, centroid) = extent(traj);
+        Entry {
+            id,
+            coeffs: coeffs.to_vec(),
+            spectrum,
+            ky_dim,
+            trajectory: traj.to_vec(),
+            method: method.to_string(),
+            time_scale: 1.0,
+            topo_entropy: None,
+            frenet: None,
+            unstable_dim: lyapunov::unstable_dim(&spectrum),
+            pose: None,
+            seed: None,
+            bbox,
+            centroid,
+            nonzero: ode::nonzero_count(coeffs, 0.0),
+            resampled: false,
+        }
+    }
+
+    /// Coefficients as a fixed-size array.
+    pub fn coeff_array(&self) -> Option<Coeffs> {
+        self.coeffs.as_slice().try_into().ok()
+    }
+}
+
+/// Initial condition of stored trajectories,
+/// before search trims their transient.
+pub const TRAJ_S0: State = [0.1, 0.1, 0.1];
+
+/// Rebuild an entry's trajectory from TRAJ_S0 with
+/// n RK4 steps of dt. Empty if coeffs are malformed.
+pub fn reintegrate(entry: &Entry, dt: f64, n: usize) -> Vec<State> {
+    match entry.coeff_array() {
+        Some(c) => ode::integrate_traj(&c, &TRAJ_S0, dt, n),
+        None => Vec::new(),
+    }
+}
+
+/// Bounding box and centroid; zeros if empty.
+fn extent(traj: &[State]) -> ([[f64; 3]; 2], [f64; 3]) {
+    if traj.is_empty() {
+        return ([[0.0; 3]; 2], [0.0; 3]);
+    }
+    let mut lo = [f64::INFINITY; 3];
+    let mut hi = [f64::NEG_INFINITY; 3];
+    let mut sum = [0.0; 3];
+    for p in traj {
+        for k in 0..3 {
+            lo[k] = lo[k].min(p[k]);
+            hi[k] = hi[k].max(p[k]);
+            sum[k] += p[k];
+        }
+    }
+    let n = traj.len() as f64;
+    ([lo, hi], sum.map(|v| v / n))
+}
+
+/// Hash coefficients for quick ID.
+pub fn coeff_hash(c: &Coeffs) -> u64 {
+    let mut h = DefaultHasher::new();
+    for &v in c.iter() {
+        v.to_bits().hash(&mut h);
+    }
+    h.finish()
+}
+
+/// Decimal places canonical_coeffs rounds to, so
+/// rescaled copies agree to the bit.
+const CANON_DIGITS: i32 = 9;
+
+/// Representative of c's class under positive time
+/// rescaling (c -> k c) and the 48 signed axis
+/// permutations (x -> ±y etc.), which all give the
+/// same attractor up to speed and orientation: c
+/// is scaled so its largest |coefficient| is 1,
+/// rounded to CANON_DIGITS places, and of its 48
+/// relabellings the lexicographically smallest is
+/// kept. Per-axis rescalings (x -> a x) are not
+/// canonicalized.
+pub fn canonical_coeffs(c: &Coeffs) -> Coeffs {
+    let m = c.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+    if m == 0.0 || !m.is_finite() {
+        return *c;
+    }
+    let p = 10f64.powi(CANON_DIGITS);
+    let c = c.map(|v| (v / m * p).round() / p + 0.0);
+    let perms = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut best = c;
+    for perm in perms {
+        for flips in 0..8 {
+            let sign = [0, 1, 2].map(|k| {
+                if flips >> k & 1 == 1 { -1.0 } else { 1.0 }
+            });
+            let t = relabel(&c, perm, sign);
+            let less = t.iter().zip(&best).find(|(a, b)| a != b);
+            if less.is_some_and(|(a, b)| a < b) {
+                best = t;
+            }
+        }
+    }
+    best
+}
+
+/// c in coordinates u with u_i = sign_i x_perm_i.
+fn relabel(c: &Coeffs, perm: [usize; 3], sign: [f64; 3]) -> Coeffs {
+    let mut out = [0.0; ode::NCOEFFS];
+    for (i, (&src, &s)) in perm.iter().zip(&sign).enumerate() {
+        for (t, p) in ode::POWERS.iter().enumerate() {
+            // x_perm_k = sign_k u_k.
+            let q = [0, 1, 2].map(|k| p[perm[k]]);
+            let f = (0..3)
+                .fold(s, |f, k| f * sign[k].powi(q[k] as i32));
+            let u = ode::POWERS.iter().position(|w| *w == q).unwrap();
+            let v = c[src * ode::NTERMS + t];
+            out[i * ode::NTERMS + u] = f * v + 0.0;
+        }
+    }
+    out
+}
+
+/// coeff_hash of canonical_coeffs: equal for
+/// systems that differ only by time scale or a
+/// signed axis permutation.
+pub fn canonical_hash(c: &Coeffs) -> u64 {
+    coeff_hash(&canonical_coeffs(c))
+}
+
+/// Save entry to dir as JSON metadata plus a
+/// `{id}.traj` trajectory blob.
+pub fn save(
+    dir: &Path,
+    entry: &Entry,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(
+        format!("{:016x}.json", entry.id),
+    );
+    let json = serde_json::to_string_pretty(&meta(entry))
+        .map_err(std::io::Error::other)?;
+    std::fs::write(path, json)?;
+    save_trajectory(dir, entry)
+}
+
+/// Save entry in the compact bincode format as
+/// `{id}.bin` plus the trajectory blob; `load` and
+/// `load_all` read both formats.
+pub fn save_binary(
+    dir: &Path,
+    entry: &Entry,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{:016x}.bin", entry.id));
+    let bytes = bincode::serialize(&meta(entry))
+        .map_err(std::io::Error::other)?;
+    std::fs::write(path, bytes)?;
+    save_trajectory(dir, entry)
+}
+
+/// Save entry gzip-compressed as `{id}.json.gz`
+/// plus a `{id}.traj.gz` blob; read back
+/// transparently alongside the other formats.
+pub fn save_gz(dir: &Path, entry: &Entry) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let f = std::fs::File::create(
+        dir.join(format!("{:016x}.json.gz", entry.id)),
+    )?;
+    let mut gz = GzEncoder::new(f, Compression::default());
+    serde_json::to_writer(&mut gz, &meta(entry))
+        .map_err(std::io::Error::other)?;
+    gz.finish()?;
+    let f = std::fs::File::create(traj_gz_path(dir, entry.id))?;
+    let mut gz = GzEncoder::new(f, Compression::default());
+    bincode::serialize_into(&mut gz, &entry.trajectory)
+        .map_err(std::io::Error::other)?;
+    gz.finish()?;
+    Ok(())
+}
+
+/// Entry without its trajectory, copied field by
+/// field so the points are never cloned.
+fn meta(entry: &Entry) -> Entry {
+    Entry {
+        id: entry.id,
+        coeffs: entry.coeffs.clone(),
+        spectrum: entry.spectrum,
+        ky_dim: entry.ky_dim,
+        trajectory: Vec::new(),
+        method: entry.method.clone(),
+        time_scale: entry.time_scale,
+        topo_entropy: entry.topo_entropy,
+        frenet: entry.frenet.clone(),
+        unstable_dim: entry.unstable_dim,
+        pose: entry.pose.clone(),
+        seed: entry.seed,
+        bbox: entry.bbox,
+        centroid: entry.centroid,
+        nonzero: entry.nonzero,
+        resampled: entry.resampled,
+    }
+}
+
+fn traj_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:016x}.traj"))
+}
+
+fn traj_gz_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{id:016x}.traj.gz"))
+}
+
+fn save_trajectory(dir: &Path, entry: &Entry) -> std::io::Result<()> {
+    let bytes = bincode::serialize(&entry.trajectory)
+        .map_err(std::io::Error::other)?;
+    std::fs::write(traj_path(dir, entry.id), bytes)
+}
+
+/// Trajectory of one entry: its blob, or the copy
+/// inlined in entry files from older versions.
+pub fn load_trajectory(
+    dir: &Path,
+    id: u64,
+) -> std::io::Result<Vec<State>> {
+    let p = traj_location(dir, id);
+    if p == traj_path(dir, id) {
+        return bincode::deserialize(&std::fs::read(p)?)
+            .map_err(std::io::Error::other);
+    }
+    if p == traj_gz_path(dir, id) {
+        let gz = GzDecoder::new(std::fs::File::open(p)?);
+        return bincode::deserialize_from(gz)
+            .map_err(std::io::Error::other);
+    }
+    Ok(load_meta(dir, id)?.trajectory)
+}
+
+/// File load_trajectory reads: the .traj or
+/// .traj.gz blob, else the entry file itself.
+pub fn traj_location(dir: &Path, id: u64) -> PathBuf {
+    [traj_path(dir, id), traj_gz_path(dir, id)]
+        .into_iter()
+        .find(|p| p.exists())
+        .unwrap_or_else(|| entry_path(dir, id))
+}
+
+/// Path of an entry's file: the first present of
+/// .json, .json.gz and .bin (.bin if none).
+fn entry_path(dir: &Path, id: u64) -> PathBuf {
+    ["json", "json.gz"]
+        .iter()
+        .map(|ext| dir.join(format!("{id:016x}.{ext}")))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| dir.join(format!("{id:016x}.bin")))
+}
+
+/// Parse a .json, .json.gz or .bin entry file.
+/// Ok(None) for other files and malformed contents.
+fn read_entry(p: &Path) -> std::io::Result<Option<Entry>> {
+    Ok(parse_entry(p)?.and_then(Result::ok))
+}
+
+/// Like read_entry, keeping the parse error of a
+/// malformed entry file. Only `{id:016x}.*` names
+/// count, so checkpoint.json is not an entry.
+fn parse_entry(
+    p: &Path,
+) -> std::io::Result<Option<Result<Entry, String>>> {
+    let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let id = name.split('.').next().unwrap_or("");
+    if id.len() != 16 || !id.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Ok(None);
+    }
+    let json = |d: &str| serde_json::from_str(d).map_err(|e| e.to_string());
+    Ok(if name.ends_with(".json") {
+        Some(json(&std::fs::read_to_string(p)?))
+    } else if name.ends_with(".json.gz") {
+        let mut data = String::new();
+        GzDecoder::new(std::fs::File::open(p)?)
+            .read_to_string(&mut data)?;
+        Some(json(&data))
+    } else if name.ends_with(".bin") {
+        let data = std::fs::read(p)?;
+        Some(bincode::deserialize(&data).map_err(|e| e.to_string()))
+    } else {
+        None
+    })
+}
+
+/// Throttled saver: buffers entries and writes
+/// them as one batch at most once per `interval`.
+/// Call flush before dropping it; entries still
+/// pending are lost.
+#[derive(Debug)]
+pub struct Writer {
+    dir: PathBuf,
+    interval: Duration,
+    pending: Vec<Entry>,
+    /// Written since the last take_saved.
+    saved: Vec<Entry>,
+    last: Instant,
+    /// Batches written so far.
+    pub writes: usize,
+    /// Write gzip files (save_gz).
+    pub compress: bool,
+}
+
+impl Writer {
+    /// A zero interval saves on every push.
+    pub fn new(dir: &Path, interval: Duration) -> Self {
+        Writer {
+            dir: dir.to_path_buf(),
+            interval,
+            pending: Vec::new(),
+            saved: Vec::new(),
+            last: Instant::now(),
+            writes: 0,
+            compress: false,
+        }
+    }
+
+    /// Queue an entry; write the batch if due.
+    pub fn push(&mut self, entry: Entry) -> std::io::Result<()> {
+        self.pending.push(entry);
+        if self.last.elapsed() >= self.interval {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write all pending entries now. Stops at the
+    /// first failed save; it and later entries stay
+    /// pending for the next flush.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.last = Instant::now();
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.writes += 1;
+        let mut done = 0;
+        let res = self.pending.iter().try_for_each(|e| {
+            if self.compress {
+                save_gz(&self.dir, e)?;
+            } else {
+                save(&self.dir, e)?;
+            }
+            done += 1;
+            Ok(())
+        });
+        self.saved.extend(self.pending.drain(..done));
+        res
+    }
+
+    /// Entries on disk since the last call.
+    pub fn take_saved(&mut self) -> Vec<Entry> {
+        std::mem::take(&mut self.saved)
+    }
+}
+
+/// Load a single entry by id, with trajectory.
+pub fn load(
+    dir: &Path,
+    id: u64,
+) -> std::io::Result<Entry> {
+    let mut e = load_meta(dir, id)?;
+    if e.trajectory.is_empty() {
+        e.trajectory = load_trajectory(dir, id)?;
+    }
+    Ok(e)
+}
+
+fn load_meta(dir: &Path, id: u64) -> std::io::Result<Entry> {
+    read_entry(&entry_path(dir, id))?.ok_or_else(|| {
+        std::io::Error::other(format!("malformed entry {id:016x}"))
+    })
+}
+
+/// Load the metadata of all entries from dir;
+/// trajectories stay on disk (see
+/// `load_trajectory`) except in old inline files.
+pub fn load_all(
+    dir: &Path,
+) -> std::io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    each(dir, |e| {
+        entries.push(e);
+        true
+    })?;
+    Ok(entries)
+}
+
+/// Result of `load_report`.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    pub entries: Vec<Entry>,
+    /// Entry files that could not be read or
+    /// parsed, with the reason.
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+/// load_all, but listing the entry files it would
+/// silently skip instead of dropping them.
+pub fn load_report(dir: &Path) -> std::io::Result<LoadReport> {
+    let mut rep = LoadReport::default();
+    if !dir.exists() {
+        return Ok(rep);
+    }
+    for item in std::fs::read_dir(dir)? {
+        let p = item?.path();
+        match parse_entry(&p) {
+            Ok(None) => {}
+            Ok(Some(Ok(e))) => rep.entries.push(e),
+            Ok(Some(Err(e))) => rep.errors.push((p, e)),
+            Err(e) => rep.errors.push((p, e.to_string())),
+        }
+    }
+    Ok(rep)
+}
+
+/// Feed each readable entry file in dir to f, one
+/// at a time, until f returns false.
+fn each(
+    dir: &Path,
+    mut f: impl FnMut(Entry) -> bool,
+) -> std::io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for item in std::fs::read_dir(dir)? {
+        // Bad files are skipped; load_report lists them.
+        if let Ok(Some(e)) = read_entry(&item?.path()) {
+            if !f(e) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sort order for `query`, largest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Lambda1,
+    KyDim,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "lambda1" => Ok(SortKey::Lambda1),
+            "ky_dim" => Ok(SortKey::KyDim),
+            _ => Err(format!("unknown sort key: {s}")),
+        }
+    }
+}
+
+/// Catalog filter; None fields are unconstrained.
+#[derive(Debug, Clone, Default)]
+pub struct QueryFilter {
+    pub min_lambda1: Option<f64>,
+    pub max_lambda1: Option<f64>,
+    pub min_dim: Option<f64>,
+    pub max_dim: Option<f64>,
+    pub method: Option<String>,
+    pub max_nonzero: Option<usize>,
+    pub sort: Option<SortKey>,
+    pub limit: Option<usize>,
+}
+
+impl QueryFilter {
+    /// Whether an entry passes the ranges and method.
+    pub fn matches(&self, e: &Entry) -> bool {
+        let within = |v: f64, lo: Option<f64>, hi: Option<f64>| {
+            lo.is_none_or(|l| v >= l) && hi.is_none_or(|h| v <= h)
+        };
+        within(e.spectrum[0], self.min_lambda1, self.max_lambda1)
+            && within(e.ky_dim, self.min_dim, self.max_dim)
+            && self.method.as_ref().is_none_or(|m| *m == e.method)
+            && self.max_nonzero.is_none_or(|k| e.nonzero <= k)
+    }
+}
+
+/// Entries passing the filter, sorted and capped.
+/// Files are parsed one at a time and only matches
+/// kept; unsorted queries stop at the limit.
+pub fn query(
+    dir: &Path,
+    filter: &QueryFilter,
+) -> std::io::Result<Vec<Entry>> {
+    let cap = filter.limit.unwrap_or(usize::MAX);
+    let mut out = Vec::new();
+    each(dir, |e| {
+        if filter.matches(&e) {
+            out.push(e);
+        }
+        filter.sort.is_some() || out.len() < cap
+    })?;
+    if let Some(k) = filter.sort {
+        let key = |e: &Entry| match k {
+            SortKey::Lambda1 => e.spectrum[0],
+            SortKey::KyDim => e.ky_dim,
+        };
+        out.sort_by(|a, b| key(b).total_cmp(&key(a)));
+    }
+    out.truncate(cap);
+    Ok(out)
+}
+
+/// Remove near-duplicates: entries within L2
+/// coefficient distance `tol` of a kept entry with
+/// larger λ1, or sharing its canonical_hash, are
+/// deleted. Returns files removed.
+pub fn dedupe(dir: &Path, tol: f64) -> std::io::Result<usize> {
+    let mut entries = load_all(dir)?;
+    entries.sort_by(|a, b| b.spectrum[0].total_cmp(&a.spectrum[0]));
+    let mut kept: Vec<&Entry> = Vec::new();
+    let mut classes = BTreeSet::new();
+    let mut removed = 0;
+    for e in &entries {
+        let class = e.coeff_array().map(|c| canonical_hash(&c));
+        let same = class.is_some_and(|h| classes.contains(&h));
+        let dup = same || kept.iter().any(|k| {
+            let d2: f64 = k
+                .coeffs
+                .iter()
+                .zip(&e.coeffs)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum();
+            d2 < tol * tol
+        });
+        if dup {
+            remove(dir, e.id)?;
+            removed += 1;
+        } else {
+            kept.push(e);
+            classes.extend(class);
+        }
+    }
+    Ok(removed)
+}
+
+/// Delete an entry's files in every format.
+fn remove(dir: &Path, id: u64) -> std::io::Result<()> {
+    for ext in ["json", "json.gz", "bin", "traj", "traj.gz"] {
+        let p = dir.join(format!("{id:016x}.{ext}"));
+        if p.exists() {
+            std::fs::remove_file(p)?;
+        }
+    }
+    Ok(())
+}
+
+/// Outcome of `merge`, in entries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeStats {
+    pub added: usize,
+    pub skipped: usize,
+    pub replaced: usize,
+    /// Entries in src whose file or trajectory
+    /// failed to load.
+    pub unreadable: usize,
+}
+
+/// Copy src's entries into dst. An id already in
+/// dst is replaced only if src has the better
+/// trajectory, else skipped: full-resolution beats
+/// resampled (store_points), then longer wins.
+/// Convergence is not stored on Entry, so it
+/// cannot decide. A dst copy that fails to load is
+/// replaced.
+pub fn merge(src: &Path, dst: &Path) -> std::io::Result<MergeStats> {
+    let rep = load_report(src)?;
+    let mut stats = MergeStats {
+        unreadable: rep.errors.len(),
+        ..Default::default()
+    };
+    for mut e in rep.entries {
+        let have = entry_path(dst, e.id).exists();
+        let Ok(theirs) = load_trajectory(src, e.id) else {
+            stats.unreadable += 1;
+            continue;
+        };
+        if have {
+            let rank = |resampled: bool, len: usize| (!resampled, len);
+            let ours = load_meta(dst, e.id).and_then(|m| {
+                Ok(rank(m.resampled, load_trajectory(dst, e.id)?.len()))
+            });
+            if let Ok(ours) = ours {
+                if rank(e.resampled, theirs.len()) <= ours {
+                    stats.skipped += 1;
+                    continue;
+                }
+            }
+            remove(dst, e.id)?;
+            stats.replaced += 1;
+        } else {
+            stats.added += 1;
+        }
+        e.trajectory = theirs;
+        save(dst, &e)?;
+    }
+    Ok(stats)
+}
+
+/// Id partition between two catalogs.
+#[derive(Debug, Default)]
+pub struct CatalogDiff {
+    pub only_a: Vec<u64>,
+    pub only_b: Vec<u64>,
+    pub shared: Vec<u64>,
+}
+
+/// Compare two catalog dirs by entry id.
+pub fn diff(
+    a: &Path,
+    b: &Path,
+) -> std::io::Result<CatalogDiff> {
+    let ids = |d| -> std::io::Result<BTreeSet<u64>> {
+        Ok(load_all(d)?.iter().map(|e| e.id).collect())
+    };
+    let (ia, ib) = (ids(a)?, ids(b)?);
+    Ok(CatalogDiff {
+        only_a: ia.difference(&ib).copied().collect(),
+        only_b: ib.difference(&ia).copied().collect(),
+        shared: ia.intersection(&ib).copied().collect(),
+    })
+}
+
+/// KY dimension buckets in `CatalogStats`: 0.1
+/// wide over [2, 3], outliers clamped to the ends.
+pub const STATS_DIM_BINS: usize = 10;
+
+/// Catalog overview for `stats`.
+#[derive(Debug)]
+pub struct CatalogStats {
+    pub count: usize,
+    /// λ1 (min, median, max); None if empty.
+    pub lambda1: Option<[f64; 3]>,
+    pub ky_dim: Histogram,
+    /// Entries per discovery method.
+    pub methods: BTreeMap<String, usize>,
+    /// The entry with the largest λ1, metadata only.
+    pub strongest: Option<Entry>,
+}
+
+/// Summarize dir; empty if it does not exist.
+pub fn stats(dir: &Path) -> std::io::Result<CatalogStats> {
+    let entries = load_all(dir)?;
+    let mut l1: Vec<f64> =
+        entries.iter().map(|e| e.spectrum[0]).collect();
+    l1.sort_by(f64::total_cmp);
+    let lambda1 = (!l1.is_empty()).then(|| {
+        let m = l1.len() / 2;
+        let med = if l1.len().is_multiple_of(2) {
+            0.5 * (l1[m - 1] + l1[m])
+        } else {
+            l1[m]
+        };
+        [l1[0], med, l1[l1.len() - 1]]
+    });
+    let mut ky_dim = Histogram {
+        lo: 2.0,
+        width: 1.0 / STATS_DIM_BINS as f64,
+        counts: vec![0; STATS_DIM_BINS],
+    };
+    for e in entries.iter().filter(|e| e.ky_dim.is_finite()) {
+        let i = ((e.ky_dim - ky_dim.lo) / ky_dim.width).max(0.0);
+        ky_dim.counts[(i as usize).min(STATS_DIM_BINS - 1)] += 1;
+    }
+    let mut methods = BTreeMap::new();
+    for e in &entries {
+        *methods.entry(e.method.clone()).or_insert(0) += 1;
+    }
+    let count = entries.len();
+    let strongest = entries
+        .into_iter()
+        .max_by(|a, b| a.spectrum[0].total_cmp(&b.spectrum[0]));
+    Ok(CatalogStats {
+        count,
+        lambda1,
+        ky_dim,
+        methods,
+        strongest,
+    })
+}
+
+/// Write trajectory as x,y,z CSV rows, preceded
+/// by `#` comment lines giving id and coefficients.
+pub fn export_csv(
+    entry: &Entry,
+    path: &Path,
+) -> std::io::Result<()> {
+    let f = std::fs::File::create(path)?;
+    let mut w = std::io::BufWriter::new(f);
+    writeln!(w, "# id: {:016x}", entry.id)?;
+    writeln!(w, "# terms: {}", ode::TERM_NAMES.join(","))?;
+    for (eq, row) in ["dx", "dy", "dz"]
+        .iter()
+        .zip(entry.coeffs.chunks(ode::NTERMS))
+    {
+        let vals: Vec<String> =
+            row.iter().map(|v| v.to_string()).collect();
+        writeln!(w, "# {eq}/dt: {}", vals.join(","))?;
+    }
+    writeln!(w, "x,y,z")?;
+    for p in &entry.trajectory {
+        writeln!(w, "{},{},{}", p[0], p[1], p[2])?;
+    }
+    w.flush()
+}
+
+/// `.npy` v1.0 header for a C-order array; padded
+/// so the data starts on a 64-byte boundary.
+fn npy_header(descr: &str, shape: &str) -> Vec<u8> {
+    let dict = format!(
+        "{{'descr': {descr}, 'fortran_order': False, \
+         'shape': {shape}, }}"
+    );
+    let pad = (64 - (11 + dict.len()) % 64) % 64;
+    let hlen = dict.len() + pad + 1;
+    let mut h = b"\x93NUMPY\x01\x00".to_vec();
+    h.extend_from_slice(&(hlen as u16).to_le_bytes());
+    h.extend_from_slice(dict.as_bytes());
+    h.extend(vec![b' '; pad]);
+    h.push(b'\n');
+    h
+}
+
+/// Write trajectory as an (N, 3) float64 `.npy`.
+pub fn export_npy(
+    entry: &Entry,
+    path: &Path,
+) -> std::io::Result<()> {
+    let f = std::fs::File::create(path)?;
+    let mut w = std::io::BufWriter::new(f);
+    let shape = format!("({}, 3)", entry.trajectory.len());
+    w.write_all(&npy_header("'<f8'", &shape))?;
+    for v in entry.trajectory.iter().flatten() {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    w.flush()
+}
+
+/// Record layout of the catalog metadata `.npy`.
+const META_DESCR: &str = "[('id', '<u8'), ('l1', '<f8'), \
+     ('l2', '<f8'), ('l3', '<f8'), ('ky_dim', '<f8'), \
+     ('unstable_dim', '<u8'), ('time_scale', '<f8')]";
+
+/// Write per-entry metadata of a catalog as a
+/// structured `.npy` (one record per entry, by id).
+pub fn export_meta_npy(
+    dir: &Path,
+    path: &Path,
+) -> std::io::Result<usize> {
+    let mut entries = load_all(dir)?;
+    entries.sort_by_key(|e| e.id);
+    let f = std::fs::File::create(path)?;
+    let mut w = std::io::BufWriter::new(f);
+    let shape = format!("({},)", entries.len());
+    w.write_all(&npy_header(META_DESCR, &shape))?;
+    for e in &entries {
+        w.write_all(&e.id.to_le_bytes())?;
+        for v in e.spectrum.iter().chain([&e.ky_dim]) {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        w.write_all(&(e.unstable_dim as u64).to_le_bytes())?;
+        w.write_all(&e.time_scale.to_le_bytes())?;
+    }
+    w.flush()?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::NCOEFFS;
+    use std::path::PathBuf;
+
+    #[test]
+    fn roundtrip() {
+        let c = [0.5; NCOEFFS];
+        let traj = vec![[1.0, 2.0, 3.0]; 5];
+        let entry = Entry::new(
+            &c,
+            [0.9, 0.0, -14.0],
+            2.06,
+            &traj,
+            "test",
+        );
+        let dir = PathBuf::from("/tmp/attractor_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        save(&dir, &entry).unwrap();
+        let loaded = load_all(&dir).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, entry.id);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn bbox_and_centroid() {
+        let traj = [[1.0, -2.0, 0.0], [3.0, 4.0, -1.0], [2.0, 1.0, 4.0]];
+        let e = Entry::new(&[0.0; NCOEFFS], [0.0; 3], 0.0, &traj, "t");
+        assert_eq!(e.bbox, [[1.0, -2.0, -1.0], [3.0, 4.0, 4.0]]);
+        assert_eq!(e.centroid, [2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn query_filters_sorts_limits() {
+        let dir = PathBuf::from("/tmp/attractor_query");
+        let _ = std::fs::remove_dir_all(&dir);
+        let traj = vec![[0.0; 3]; 2];
+        for (i, (l, d, m)) in [
+            (0.2, 2.1, "random"),
+            (0.6, 2.05, "random"),
+            (0.9, 2.3, "evolve"),
+            (0.7, 2.4, "random"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut c = [0.0; NCOEFFS];
+            c[0] = i as f64;
+            save(&dir, &Entry::new(&c, [l, 0.0, -1.0], d, &traj, m))
+                .unwrap();
+        }
+        std::fs::write(dir.join("junk.json"), "{").unwrap();
+        let f = QueryFilter {
+            min_lambda1: Some(0.5),
+            method: Some("random".into()),
+            sort: Some(SortKey::KyDim),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let hits = query(&dir, &f).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].ky_dim, 2.4);
+        let dense = QueryFilter {
+            max_nonzero: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(query(&dir, &dense).unwrap().len(), 1);
+        let all = QueryFilter::default();
+        assert_eq!(query(&dir, &all).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn binary_roundtrip_and_mixed_load() {
+        let dir = PathBuf::from("/tmp/attractor_bin");
+        let _ = std::fs::remove_dir_all(&dir);
+        let traj: Vec<State> = (0..5000)
+            .map(|i| {
+                let t = i as f64 * 0.01;
+                [t.sin(), t.cos() * 3.0, t.sqrt()]
+            })
+            .collect();
+        let mk = |v: f64| {
+            Entry::new(&[v; NCOEFFS], [v, 0.0, -1.0], 2.06, &traj, "t")
+        };
+        let (a, b) = (mk(0.5), mk(0.25));
+        save_binary(&dir, &a).unwrap();
+        // b in the old inline JSON layout.
+        std::fs::write(
+            dir.join(format!("{:016x}.json", b.id)),
+            serde_json::to_string_pretty(&b).unwrap(),
+        )
+        .unwrap();
+        let back = load(&dir, a.id).unwrap();
+        assert_eq!(back.coeffs, a.coeffs);
+        assert_eq!(back.trajectory, a.trajectory);
+        assert_eq!(back.bbox, a.bbox);
+        assert_eq!(load_all(&dir).unwrap().len(), 2);
+        let size = |ext: &str, id: u64| {
+            let p = dir.join(format!("{id:016x}.{ext}"));
+            std::fs::metadata(p).unwrap().len()
+        };
+        let bin = size("bin", a.id) + size("traj", a.id);
+        assert!(bin * 3 < size("json", b.id));
+    }
+
+    #[test]
+    fn trajectory_stored_apart() {
+        let dir = PathBuf::from("/tmp/attractor_split");
+        let _ = std::fs::remove_dir_all(&dir);
+        let traj = vec![[1.0, 2.0, 3.0]; 1000];
+        let e = Entry::new(&[0.5; NCOEFFS], [0.9, 0.0, -1.0], 2.0, &traj, "t");
+        save(&dir, &e).unwrap();
+        let all = load_all(&dir).unwrap();
+        assert!(all[0].trajectory.is_empty());
+        assert_eq!(all[0].bbox, e.bbox);
+        assert_eq!(load_trajectory(&dir, e.id).unwrap(), traj);
+        assert_eq!(load(&dir, e.id).unwrap().trajectory, traj);
+        // Inline trajectories from older files still load.
+        let old = serde_json::to_string(&e).unwrap();
+        std::fs::remove_file(traj_path(&dir, e.id)).unwrap();
+        let p = dir.join(format!("{:016x}.json", e.id));
+        std::fs::write(&p, old).unwrap();
+        assert_eq!(load_trajectory(&dir, e.id).unwrap(), traj);
+        assert_eq!(traj_location(&dir, e.id), p);
+    }
+
+    #[test]
+    fn reintegrate_lorenz_bounded() {
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let coarse = ode::integrate_traj(&c, &TRAJ_S0, 0.01, 2000);
+        let e = Entry::new(&c, [0.9, 0.0, -14.5], 2.06, &coarse, "t");
+        let fine = reintegrate(&e, 0.001, 20000);
+        assert_eq!(fine.len(), 20001);
+        assert_eq!(fine[0], coarse[0]);
+        // Same start at 10x resolution: agrees early on.
+        for k in 0..3 {
+            assert!((fine[1000][k] - coarse[100][k]).abs() < 1e-3);
+        }
+        let tail = &fine[10000..];
+        assert!(tail.iter().all(|p| p.iter().all(|v| v.abs() < 60.0)));
+        let z = tail.iter().map(|p| p[2]).sum::<f64>() / tail.len() as f64;
+        assert!((z - 23.5).abs() < 3.0, "mean z={z}");
+    }
+
+    #[test]
+    fn diff_partition() {
+        let traj = vec![[0.0; 3]; 2];
+        let mk = |v: f64| {
+            let c = [v; NCOEFFS];
+            Entry::new(&c, [0.0; 3], 2.0, &traj, "test")
+        };
+        let (e1, e2, e3) = (mk(1.0), mk(2.0), mk(3.0));
+        let a = PathBuf::from("/tmp/attractor_diff_a");
+        let b = PathBuf::from("/tmp/attractor_diff_b");
+        let _ = std::fs::remove_dir_all(&a);
+        let _ = std::fs::remove_dir_all(&b);
+        for e in [&e1, &e2] {
+            save(&a, e).unwrap();
+        }
+        for e in [&e2, &e3] {
+            save(&b, e).unwrap();
+        }
+        let d = diff(&a, &b).unwrap();
+        assert_eq!(d.only_a, vec![e1.id]);
+        assert_eq!(d.only_b, vec![e3.id]);
+        assert_eq!(d.shared, vec![e2.id]);
+        let _ = std::fs::remove_dir_all(&a);
+        let _ = std::fs::remove_dir_all(&b);
+    }
+
+    #[test]
+    fn dedupe_keeps_strongest() {
+        let dir = PathBuf::from("/tmp/attractor_dedupe");
+        let _ = std::fs::remove_dir_all(&dir);
+        let traj = vec![[0.0; 3]; 2];
+        let mk = |v: f64, l1: f64| {
+            let c = [v; NCOEFFS];
+            Entry::new(&c, [l1, 0.0, -1.0], 2.0, &traj, "t")
+        };
+        // Three near-duplicates plus one distinct.
+        let mut distinct = mk(1.5, 0.1);
+        distinct.coeffs[0] = 0.0;
+        distinct.id = coeff_hash(&distinct.coeff_array().unwrap());
+        let es = [
+            mk(1.0001, 0.5),
+            mk(1.0, 0.9),
+            mk(1.0002, 0.7),
+            distinct,
+        ];
+        for e in &es {
+            save(&dir, e).unwrap();
+        }
+        assert_eq!(dedupe(&dir, 0.01).unwrap(), 2);
+        let ids: BTreeSet<u64> =
+            load_all(&dir).unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, BTreeSet::from([es[1].id, es[3].id]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn canonical_merges_rescaled_and_relabelled() {
+        let lor = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let want = canonical_coeffs(&lor);
+        assert_eq!(want.iter().fold(0.0f64, |m, v| m.max(v.abs())), 1.0);
+        for k in [0.3, 1.0, 7.0, 1e3] {
+            assert_eq!(canonical_coeffs(&lor.map(|v| k * v)), want);
+        }
+        // Swap x and y, flip z.
+        let swapped = relabel(&lor, [1, 0, 2], [1.0, 1.0, -1.0]);
+        assert_ne!(swapped, lor);
+        assert_eq!(canonical_hash(&swapped), canonical_hash(&lor));
+        let other = ode::lorenz_coeffs(10.0, 30.0, 8.0 / 3.0);
+        assert_ne!(canonical_hash(&other), canonical_hash(&lor));
+        // Scaled copies now dedupe exactly.
+        let dir = Path::new("/tmp/attractor_canon");
+        let _ = std::fs::remove_dir_all(dir);
+        let t = [[0.1; 3], [0.2; 3]];
+        for (k, l1) in [(1.0, 0.9), (2.0, 1.8)] {
+            let c = lor.map(|v| k * v);
+            save(dir, &Entry::new(&c, [l1, 0.0, -1.0], 2.06, &t, "t"))
+                .unwrap();
+        }
+        assert_eq!(dedupe(dir, 1e-6).unwrap(), 1);
+        assert_eq!(load_all(dir).unwrap()[0].spectrum[0], 1.8);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn load_report_lists_bad_files() {
+        let dir = Path::new("/tmp/attractor_loadrep");
+        let _ = std::fs::remove_dir_all(dir);
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let t = [[0.1; 3], [0.2; 3]];
+        save(dir, &Entry::new(&c, [0.9, 0.0, -14.5], 2.06, &t, "t"))
+            .unwrap();
+        std::fs::write(dir.join("0000000000000001.json"), "{").unwrap();
+        std::fs::write(dir.join("0000000000000002.json.gz"), "x").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        std::fs::write(dir.join("checkpoint.json"), "{}").unwrap();
+        assert_eq!(load_all(dir).unwrap().len(), 1);
+        let rep = load_report(dir).unwrap();
+        assert_eq!(rep.entries.len(), 1);
+        let mut bad: Vec<String> = rep
+            .errors
+            .iter()
+            .map(|(p, _)| p.file_name().unwrap().to_string_lossy().into())
+            .collect();
+        bad.sort();
+        assert_eq!(bad, ["0000000000000001.json", "0000000000000002.json.gz"]);
+        assert!(rep.errors.iter().all(|(_, e)| !e.is_empty()));
+        // A readable entry with a corrupt blob.
+        let c2 = c.map(|v| 2.0 * v);
+        let d = Entry::new(&c2, [1.8, 0.0, -29.0], 2.06, &t, "t");
+        save(dir, &d).unwrap();
+        std::fs::write(traj_path(dir, d.id), "x").unwrap();
+        assert_eq!(traj_location(dir, d.id), traj_path(dir, d.id));
+        let dst = Path::new("/tmp/attractor_loadrep_dst");
+        let _ = std::fs::remove_dir_all(dst);
+        let m = merge(dir, dst).unwrap();
+        assert_eq!((m.added, m.unreadable), (1, 3));
+        let _ = std::fs::remove_dir_all(dir);
+        let _ = std::fs::remove_dir_all(dst);
+    }
+
+    #[test]
+    fn csv_self_describing() {
+        let mut c = [0.0; NCOEFFS];
+        c[1] = -10.0;
+        let traj = vec![[1.0, 2.0, 3.0]];
+        let e = Entry::new(&c, [0.0; 3], 0.0, &traj, "t");
+        let path = PathBuf::from("/tmp/attractor_test.csv");
+        export_csv(&e, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], format!("# id: {:016x}", e.id));
+        assert!(lines[2].starts_with("# dx/dt: 0,-10,0,"));
+        assert_eq!(&lines[5..], ["x,y,z", "1,2,3"]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn npy_layout() {
+        let traj = vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let c = [0.0; NCOEFFS];
+        let e = Entry::new(&c, [0.0; 3], 0.0, &traj, "t");
+        let path = PathBuf::from("/tmp/attractor_test.npy");
+        export_npy(&e, &path).unwrap();
+        let b = std::fs::read(&path).unwrap();
+        assert_eq!(&b[..8], b"\x93NUMPY\x01\x00");
+        let hlen = u16::from_le_bytes([b[8], b[9]]) as usize;
+        assert_eq!((10 + hlen) % 64, 0);
+        let h = std::str::from_utf8(&b[10..10 + hlen]).unwrap();
+        assert!(h.starts_with("{'descr': '<f8', "));
+        assert!(h.contains("'fortran_order': False"));
+        assert!(h.contains("'shape': (2, 3)"));
+        assert!(h.ends_with('\n'));
+        let data: Vec<f64> = b[10 + hlen..]
+            .chunks(8)
+            .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(data, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn meta_npy_layout() {
+        let dir = PathBuf::from("/tmp/attractor_meta_npy");
+        let _ = std::fs::remove_dir_all(&dir);
+        let traj = vec![[0.0; 3]; 2];
+        let mut ids = Vec::new();
+        for v in [1.0, 2.0] {
+            let e = Entry::new(
+                &[v; NCOEFFS], [0.9, 0.0, -14.0], 2.06, &traj, "t",
+            );
+            ids.push(e.id);
+            save(&dir, &e).unwrap();
+        }
+        ids.sort();
+        let path = PathBuf::from("/tmp/attractor_meta_test.npy");
+        assert_eq!(export_meta_npy(&dir, &path).unwrap(), 2);
+        let b = std::fs::read(&path).unwrap();
+        assert_eq!(&b[..8], b"\x93NUMPY\x01\x00");
+        let hlen = u16::from_le_bytes([b[8], b[9]]) as usize;
+        assert_eq!((10 + hlen) % 64, 0);
+        let h = std::str::from_utf8(&b[10..10 + hlen]).unwrap();
+        assert!(h.starts_with(&format!("{{'descr': {META_DESCR}, ")));
+        assert!(h.contains("'fortran_order': False"));
+        assert!(h.contains("'shape': (2,)"));
+        assert!(h.ends_with('\n'));
+        // 7 fields of 8 bytes per record, sorted by id.
+        let data = &b[10 + hlen..];
+        assert_eq!(data.len(), 2 * 7 * 8);
+        let word = |i: usize| -> [u8; 8] {
+            data[i * 8..i * 8 + 8].try_into().unwrap()
+        };
+        assert_eq!(u64::from_le_bytes(word(0)), ids[0]);
+        assert_eq!(u64::from_le_bytes(word(7)), ids[1]);
+        assert_eq!(f64::from_le_bytes(word(1)), 0.9);
+        assert_eq!(f64::from_le_bytes(word(4)), 2.06);
+        assert_eq!(u64::from_le_bytes(word(5)), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn writer_batches_within_interval() {
+        let dir = PathBuf::from("/tmp/attractor_writer");
+        let _ = std::fs::remove_dir_all(&dir);
+        let traj = vec![[0.0; 3]; 2];
+        let mk = |v: f64| {
+            Entry::new(&[v; NCOEFFS], [0.0; 3], 2.0, &traj, "t")
+        };
+        let mut w = Writer::new(&dir, Duration::from_millis(200));
+        for i in 0..5 {
+            w.push(mk(i as f64)).unwrap();
+        }
+        assert_eq!(w.writes, 0);
+        assert!(load_all(&dir).unwrap().is_empty());
+        std::thread::sleep(Duration::from_millis(250));
+        w.push(mk(5.0)).unwrap();
+        assert_eq!(w.writes, 1);
+        assert_eq!(load_all(&dir).unwrap().len(), 6);
+        w.push(mk(6.0)).unwrap();
+        w.flush().unwrap();
+        assert_eq!(load_all(&dir).unwrap().len(), 7);
+        assert_eq!(w.take_saved().len(), 7);
+        assert!(w.take_saved().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_flush_keeps_pending() {
+        // A file where the catalog dir should be.
+        let dir = PathBuf::from("/tmp/attractor_writer_fail");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::write(&dir, "").unwrap();
+        let traj = vec![[0.0; 3]; 2];
+        let mut w = Writer::new(&dir, Duration::from_secs(60));
+        for v in [1.0, 2.0] {
+            let e = Entry::new(&[v; NCOEFFS], [0.0; 3], 2.0, &traj, "t");
+            w.push(e).unwrap();
+        }
+        assert!(w.flush().is_err());
+        assert!(w.take_saved().is_empty());
+        std::fs::remove_file(&dir).unwrap();
+        w.flush().unwrap();
+        assert_eq!(w.take_saved().len(), 2);
+        assert_eq!(load_all(&dir).unwrap().len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merge_keeps_longer_trajectory() {
+        let (a, b) = ("/tmp/attractor_merge_a", "/tmp/attractor_merge_b");
+        let (a, b) = (Path::new(a), Path::new(b));
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+        let lor = |rho| ode::lorenz_coeffs(10.0, rho, 8.0 / 3.0);
+        let e = |rho, n| {
+            let c = lor(rho);
+            let t = ode::integrate_traj(&c, &TRAJ_S0, 0.01, n);
+            Entry::new(&c, [0.9, 0.0, -14.5], 2.06, &t, "t")
+        };
+        // Shared 28 longer in a, shared 30 longer in b.
+        save(a, &e(28.0, 20)).unwrap();
+        save(a, &e(30.0, 5)).unwrap();
+        save(a, &e(32.0, 5)).unwrap();
+        save(b, &e(28.0, 10)).unwrap();
+        save_binary(b, &e(30.0, 10)).unwrap();
+        let s = merge(a, b).unwrap();
+        let want = MergeStats {
+            added: 1,
+            skipped: 1,
+            replaced: 1,
+            unreadable: 0,
+        };
+        assert_eq!(s, want);
+        let id = coeff_hash(&lor(28.0));
+        assert_eq!(load(b, id).unwrap().trajectory.len(), 21);
+        let id = coeff_hash(&lor(30.0));
+        assert_eq!(load(b, id).unwrap().trajectory.len(), 11);
+        assert_eq!(load_all(b).unwrap().len(), 3);
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+
+    #[test]
+    fn merge_prefers_full_resolution() {
+        let (a, b) = ("/tmp/attractor_merge_ra", "/tmp/attractor_merge_rb");
+        let (a, b) = (Path::new(a), Path::new(b));
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+        let lor = |rho| ode::lorenz_coeffs(10.0, rho, 8.0 / 3.0);
+        let e = |rho, n, resampled| {
+            let c = lor(rho);
+            let t = ode::integrate_traj(&c, &TRAJ_S0, 0.01, n);
+            let mut e = Entry::new(&c, [0.9, 0.0, -14.5], 2.06, &t, "t");
+            e.resampled = resampled;
+            e
+        };
+        // 28: full in a beats a longer resampled b.
+        // 30: resampled in a loses to a shorter full b.
+        // 32: b's trajectory is corrupt; a's repairs it.
+        save(a, &e(28.0, 5, false)).unwrap();
+        save(a, &e(30.0, 20, true)).unwrap();
+        save(a, &e(32.0, 5, false)).unwrap();
+        save(b, &e(28.0, 20, true)).unwrap();
+        save(b, &e(30.0, 5, false)).unwrap();
+        let broken = e(32.0, 20, false);
+        save_binary(b, &broken).unwrap();
+        std::fs::write(traj_location(b, broken.id), b"junk").unwrap();
+        let s = merge(a, b).unwrap();
+        let want = MergeStats {
+            added: 0,
+            skipped: 1,
+            replaced: 2,
+            unreadable: 0,
+        };
+        assert_eq!(s, want);
+        let got = |rho| load(b, coeff_hash(&lor(rho))).unwrap();
+        assert!(!got(28.0).resampled);
+        assert_eq!(got(28.0).trajectory.len(), 6);
+        assert_eq!(got(30.0).trajectory.len(), 6);
+        assert_eq!(got(32.0).trajectory.len(), 6);
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+
+    #[test]
+    fn gzip_roundtrip_and_mixed_load() {
+        let dir = Path::new("/tmp/attractor_gz");
+        let _ = std::fs::remove_dir_all(dir);
+        let lor = |rho| ode::lorenz_coeffs(10.0, rho, 8.0 / 3.0);
+        let e = |rho| {
+            let c = lor(rho);
+            let t = ode::integrate_traj(&c, &TRAJ_S0, 0.01, 5000);
+            Entry::new(&c, [0.9, 0.0, -14.5], 2.06, &t, "t")
+        };
+        let (a, b) = (e(28.0), e(30.0));
+        save_gz(dir, &a).unwrap();
+        save(dir, &b).unwrap();
+        let got = load(dir, a.id).unwrap();
+        assert_eq!(got.trajectory, a.trajectory);
+        assert_eq!(got.coeffs, a.coeffs);
+        assert_eq!(load_all(dir).unwrap().len(), 2);
+        let size = |n: String| std::fs::metadata(dir.join(n)).unwrap().len();
+        let (zt, t) = (
+            size(format!("{:016x}.traj.gz", a.id)),
+            size(format!("{:016x}.traj", b.id)),
+        );
+        assert!(zt < t, "{zt} vs {t}");
+        remove(dir, a.id).unwrap();
+        assert_eq!(load_all(dir).unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn stats_summarize_catalog() {
+        let dir = Path::new("/tmp/attractor_stats");
+        let _ = std::fs::remove_dir_all(dir);
+        let s = stats(dir).unwrap();
+        assert_eq!((s.count, s.lambda1), (0, None));
+        let t = [[0.1; 3], [0.2; 3]];
+        let runs = [(28.0, 0.9, "a"), (30.0, 0.5, "b"), (32.0, 1.2, "a")];
+        for (rho, l1, m) in runs {
+            let c = ode::lorenz_coeffs(10.0, rho, 8.0 / 3.0);
+            let e = Entry::new(&c, [l1, 0.0, -14.5], 2.06, &t, m);
+            save(dir, &e).unwrap();
+        }
+        let s = stats(dir).unwrap();
+        assert_eq!(s.count, 3);
+        assert_eq!(s.lambda1, Some([0.5, 0.9, 1.2]));
+        assert_eq!(s.ky_dim.counts[0], 3);
+        assert_eq!(s.methods["a"], 2);
+        assert_eq!(s.methods["b"], 1);
+        assert_eq!(s.strongest.unwrap().spectrum[0], 1.2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,123 +1,603 @@
-// Classify dynamics of a 3D quadratic ODE system.
-
-use crate::lyapunov::{self, LyapData};
-use crate::ode::{self, Coeffs, State};
-
-#[derive(Debug, Clone)]
-pub enum Dynamics {
-    Divergent,
-    FixedPoint,
-    Cycle,
-    Chaotic(LyapData),
-}
-
-/// Thresholds.
-const DIV_THRESH: f64 = 1e6;
-const FP_VAR: f64 = 1e-4;
-const CHAOS_THRESH: f64 = 0.01;
-const DT: f64 = 0.005;
-const TRANSIENT: usize = 1000;
-const CLASSIFY_STEPS: usize = 5000;
-const LYAP_STEPS: usize = 30000;
-const RENORM: usize = 10;
-
-/// Classify a system from its coefficients.
-pub fn classify(c: &Coeffs) -> Dynamics {
-    let s0: State = [0.1, 0.1, 0.1];
-    let mut s = s0;
-
-    // Transient integration.
-    for _ in 0..TRANSIENT {
-        s = ode::rk4_step(c, &s, DT);
-        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
-        if r2 > DIV_THRESH || !r2.is_finite() {
-            return Dynamics::Divergent;
-        }
-    }
-
-    // Collect trajectory stats.
-    let mut mean = [0.0f64; 3];
-    let mut var = [0.0f64; 3];
-    let n = CLASSIFY_STEPS;
-    let mut traj_s = s;
-    for _ in 0..n {
-        traj_s = ode::rk4_step(c, &traj_s, DT);
-        let r2 = traj_s[0]*traj_s[0]
-            + traj_s[1]*traj_s[1]
-            + traj_s[2]*traj_s[2];
-        if r2 > DIV_THRESH || !r2.is_finite() {
-            return Dynamics::Divergent;
-        }
-        for i in 0..3 {
-            mean[i] += traj_s[i];
-        }
-    }
-    for i in 0..3 {
-        mean[i] /= n as f64;
-    }
-
-    // Variance pass.
-    traj_s = s;
-    for _ in 0..n {
-        traj_s = ode::rk4_step(c, &traj_s, DT);
-        for i in 0..3 {
-            let d = traj_s[i] - mean[i];
-            var[i] += d * d;
-        }
-    }
-    let total_var = (var[0] + var[1] + var[2])
-        / n as f64;
-    if total_var < FP_VAR {
-        return Dynamics::FixedPoint;
-    }
-
-    // Lyapunov spectrum.
-    match lyapunov::full_spectrum(
-        c, &s0, DT, TRANSIENT, LYAP_STEPS, RENORM,
-    ) {
-        None => Dynamics::Divergent,
-        Some(data) => {
-            if data.spectrum[0] > CHAOS_THRESH {
-                Dynamics::Chaotic(data)
-            } else {
-                Dynamics::Cycle
-            }
-        }
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ode::lorenz_coeffs;
-
-    #[test]
-    fn lorenz_is_chaotic() {
-        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
-        match classify(&c) {
-            Dynamics::Chaotic(d) => {
-                assert!(d.spectrum[0] > 0.3);
-            }
-            other => {
-                panic!("expected chaotic, got {other:?}");
-            }
-        }
-    }
-
-    #[test]
-    fn damped_is_fixed_point() {
-        // dx/dt = -x, dy/dt = -y, dz/dt = -z
-        let mut c = [0.0; 30];
-        c[1] = -1.0;    // eq0: -x
-        c[12] = -1.0;   // eq1: -y
-        c[23] = -1.0;   // eq2: -z
-        match classify(&c) {
-            Dynamics::FixedPoint => {}
-            other => {
-                panic!(
-                    "expected fixed point, got {other:?}"
-                );
-            }
-        }
-    }
-}
+// Classify dynamics of a 3D quadratic ODE system.
+
+use crate::analysis;
+use crate::lyapunov::{
+    self, EarlyStop, LyapData, RenormMode, SpectrumError,
+};
+use crate::ode::{self, Coeffs, State};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub enum Dynamics {
+    /// Escaped at integration step `at_step`, or
+    /// during the spectrum run, which starts after
+    /// the pre-screen's steps and reports that count.
+    Divergent { at_step: usize },
+    FixedPoint,
+    /// Periodic; period in time units if estimated.
+    Cycle { period: Option<f64> },
+    /// Quasi-periodic: two near-zero exponents.
+    Torus,
+    Chaotic(LyapData),
+    /// λ1 and λ2 both past the chaos threshold.
+    Hyperchaotic(LyapData),
+}
+
+/// Classification options. Defaults are the
+/// constants below.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassifyConfig {
+    /// Judge λ1 relative to the system's own rate
+    /// (mean speed / RMS radius) instead of the
+    /// absolute chaos_thresh.
+    pub calibrate: bool,
+    /// Cut the spectrum short for clear non-chaos.
+    pub early_stop: Option<EarlyStop>,
+    /// Initial conditions tried (see
+    /// classify_robust); 0 or 1 means S0 only.
+    pub starts: usize,
+    /// Squared radius counted as divergence.
+    pub div_thresh: f64,
+    /// Total variance below which motion is a
+    /// fixed point.
+    pub fp_var: f64,
+    /// λ1 above this is chaos.
+    pub chaos_thresh: f64,
+    pub dt: f64,
+    /// Use ode::suggest_dt per system instead of dt.
+    pub adaptive_dt: bool,
+    pub transient: usize,
+    /// Steps of the divergence and variance pass.
+    pub classify_steps: usize,
+    pub lyap_steps: usize,
+    /// Steps between Gram-Schmidt renorms.
+    pub renorm: usize,
+    /// Renorm adaptively at this ceiling instead
+    /// (see RenormMode::Adaptive).
+    pub renorm_ceiling: Option<f64>,
+}
+
+impl Default for ClassifyConfig {
+    fn default() -> Self {
+        ClassifyConfig {
+            calibrate: false,
+            early_stop: None,
+            starts: 0,
+            div_thresh: DIV_THRESH,
+            fp_var: FP_VAR,
+            chaos_thresh: CHAOS_THRESH,
+            dt: DT,
+            adaptive_dt: false,
+            transient: TRANSIENT,
+            classify_steps: CLASSIFY_STEPS,
+            lyap_steps: LYAP_STEPS,
+            renorm: RENORM,
+            renorm_ceiling: None,
+        }
+    }
+}
+
+/// Default thresholds.
+const DIV_THRESH: f64 = 1e6;
+const FP_VAR: f64 = 1e-4;
+const CHAOS_THRESH: f64 = 0.01;
+/// Calibrated threshold, in units of the rate.
+const CALIB_THRESH: f64 = 0.005;
+const DT: f64 = 0.005;
+const TRANSIENT: usize = 1000;
+const CLASSIFY_STEPS: usize = 5000;
+const LYAP_STEPS: usize = 30000;
+const RENORM: usize = 10;
+/// Adaptive ceiling for a spectrum retried after
+/// its tangents collapsed.
+const RETRY_CEILING: f64 = 100.0;
+/// Floor on λ2 for hyperchaos.
+const HYPER_THRESH: f64 = 0.05;
+/// Band around zero for torus exponents.
+const TORUS_BAND: f64 = 0.01;
+/// Wider band in units of the exponent's
+/// convergence spread: a zero exponent's finite-time
+/// error decays only as 1/T, so at LYAP_STEPS it
+/// can exceed TORUS_BAND. The presets' λ1 all
+/// clear 6 spreads.
+const TORUS_SPREAD: f64 = 4.0;
+/// Torus λ3 must lie below -TORUS_CONTRACT.
+const TORUS_CONTRACT: f64 = 0.05;
+/// Pre-screen: |ds/dt| below SETTLE_SPEED for
+/// SETTLE_STEPS straight steps means a fixed point.
+const SETTLE_SPEED: f64 = 1e-3;
+const SETTLE_STEPS: usize = 100;
+
+/// Quasi-periodic: the two largest exponents sit
+/// near zero and the third contracts. Near zero is
+/// within TORUS_BAND or TORUS_SPREAD times the
+/// exponent's convergence spread. Checked before
+/// the chaos threshold: a λ1 inside its own noise
+/// is no evidence of stretching.
+fn torus(spec: &[f64; 3], conv: &[f64; 3]) -> bool {
+    let zero = |i: usize| {
+        spec[i].abs() < TORUS_BAND.max(TORUS_SPREAD * conv[i])
+    };
+    zero(0) && zero(1) && spec[2] < -TORUS_CONTRACT
+}
+
+/// Chaotic or hyperchaotic by the count of sorted
+/// exponents above `thresh`; None if λ1 is not.
+/// A 3D dissipative flow has Σλ < 0 and λ2 = 0
+/// along the flow, so two positive exponents are
+/// rare there and mostly flag conservative or
+/// poorly converged systems. λ2 must also clear
+/// HYPER_THRESH, since the finite-time flow
+/// exponent wanders about ±0.01 (Lorenz: 0.013).
+fn chaotic(data: LyapData, thresh: f64) -> Option<Dynamics> {
+    let mut spec = data.spectrum;
+    spec.sort_by(|a, b| b.total_cmp(a));
+    if spec[0] <= thresh {
+        None
+    } else if spec[1] > thresh.max(HYPER_THRESH) {
+        Some(Dynamics::Hyperchaotic(data))
+    } else {
+        Some(Dynamics::Chaotic(data))
+    }
+}
+
+/// λ1 threshold for a system with the given rate.
+fn chaos_thresh(p: &ClassifyConfig, rate: f64) -> f64 {
+    if p.calibrate && rate.is_finite() && rate > 0.0 {
+        CALIB_THRESH * rate
+    } else {
+        p.chaos_thresh
+    }
+}
+
+/// Classify a system with default parameters.
+pub fn classify(c: &Coeffs) -> Dynamics {
+    classify_from(c, &S0)
+}
+
+/// Classify from s0 with default parameters.
+pub fn classify_from(c: &Coeffs, s0: &State) -> Dynamics {
+    classify_from_with(c, s0, &ClassifyConfig::default())
+}
+
+/// Default initial condition.
+pub const S0: State = [0.1, 0.1, 0.1];
+
+/// Classify a system from its coefficients.
+pub fn classify_with(
+    c: &Coeffs,
+    p: &ClassifyConfig,
+) -> Dynamics {
+    if p.starts > 1 {
+        robust_with(c, p.starts, p)
+    } else {
+        classify_from_with(c, &S0, p)
+    }
+}
+
+/// Seed of the extra starts in classify_robust.
+const ROBUST_SEED: u64 = 0x5eed;
+/// Half-width of the cube extra starts come from.
+const ROBUST_EXTENT: f64 = 1.0;
+
+/// Interest rank: Chaotic > Hyperchaotic > Torus
+/// > Cycle > FixedPoint > Divergent.
+fn rank(d: &Dynamics) -> u8 {
+    match d {
+        Dynamics::Divergent { .. } => 0,
+        Dynamics::FixedPoint => 1,
+        Dynamics::Cycle { .. } => 2,
+        Dynamics::Torus => 3,
+        Dynamics::Hyperchaotic(_) => 4,
+        Dynamics::Chaotic(_) => 5,
+    }
+}
+
+/// Most interesting verdict over S0 and n - 1
+/// seeded random starts in [-1, 1]³; stops at the
+/// first chaotic one.
+pub fn classify_robust(c: &Coeffs, n_starts: usize) -> Dynamics {
+    robust_with(c, n_starts, &ClassifyConfig::default())
+}
+
+fn robust_with(
+    c: &Coeffs,
+    n_starts: usize,
+    p: &ClassifyConfig,
+) -> Dynamics {
+    let mut rng = StdRng::seed_from_u64(ROBUST_SEED);
+    let mut best = classify_from_with(c, &S0, p);
+    for _ in 1..n_starts {
+        if matches!(best, Dynamics::Chaotic(_)) {
+            break;
+        }
+        let s0 = [(); 3].map(|_| {
+            rng.gen_range(-ROBUST_EXTENT..ROBUST_EXTENT)
+        });
+        let d = classify_from_with(c, &s0, p);
+        if rank(&d) > rank(&best) {
+            best = d;
+        }
+    }
+    best
+}
+
+/// Classify the motion started at s0.
+pub fn classify_from_with(
+    c: &Coeffs,
+    s0: &State,
+    p: &ClassifyConfig,
+) -> Dynamics {
+    let mut s = *s0;
+    let dt = if p.adaptive_dt {
+        ode::suggest_dt(c, s0)
+    } else {
+        p.dt
+    };
+
+    // Consecutive near-still steps.
+    let mut still = 0;
+    let mut settled = |s: &State| {
+        let f = ode::rhs(c, s);
+        if f[0]*f[0]+f[1]*f[1]+f[2]*f[2] < SETTLE_SPEED * SETTLE_SPEED {
+            still += 1;
+        } else {
+            still = 0;
+        }
+        still >= SETTLE_STEPS
+    };
+
+    // Transient integration.
+    for step in 0..p.transient {
+        s = ode::rk4_step(c, &s, dt);
+        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
+        if r2 > p.div_thresh || !r2.is_finite() {
+            return Dynamics::Divergent { at_step: step };
+        }
+        if settled(&s) {
+            return Dynamics::FixedPoint;
+        }
+    }
+
+    // Collect trajectory stats.
+    let mut mean = [0.0f64; 3];
+    let mut var = [0.0f64; 3];
+    let n = p.classify_steps.max(1);
+    let mut traj_s = s;
+    for step in 0..n {
+        traj_s = ode::rk4_step(c, &traj_s, dt);
+        let r2 = traj_s[0]*traj_s[0]
+            + traj_s[1]*traj_s[1]
+            + traj_s[2]*traj_s[2];
+        if r2 > p.div_thresh || !r2.is_finite() {
+            let at_step = p.transient + step;
+            return Dynamics::Divergent { at_step };
+        }
+        if settled(&traj_s) {
+            return Dynamics::FixedPoint;
+        }
+        for (m, v) in mean.iter_mut().zip(traj_s) {
+            *m += v;
+        }
+    }
+    for m in mean.iter_mut() {
+        *m /= n as f64;
+    }
+
+    // Variance pass.
+    traj_s = s;
+    let mut speed = 0.0;
+    for _ in 0..n {
+        traj_s = ode::rk4_step(c, &traj_s, dt);
+        for i in 0..3 {
+            let d = traj_s[i] - mean[i];
+            var[i] += d * d;
+        }
+        if p.calibrate {
+            let f = ode::rhs(c, &traj_s);
+            speed += (f[0]*f[0]+f[1]*f[1]+f[2]*f[2])
+                .sqrt();
+        }
+    }
+    let total_var = (var[0] + var[1] + var[2])
+        / n as f64;
+    if total_var < p.fp_var {
+        return Dynamics::FixedPoint;
+    }
+    let rate = speed / n as f64 / total_var.sqrt();
+
+    // Lyapunov spectrum.
+    let spectrum = |mode: RenormMode| {
+        lyapunov::full_spectrum_with(
+            c,
+            s0,
+            dt,
+            p.transient,
+            p.lyap_steps,
+            mode,
+            p.early_stop.as_ref(),
+        )
+    };
+    let mode = match p.renorm_ceiling {
+        Some(ceiling) => RenormMode::Adaptive { ceiling },
+        None => RenormMode::Fixed(p.renorm),
+    };
+    // Collapsed tangents are the renorm interval's
+    // fault, not the orbit's: retry adaptively, or
+    // every step if already adaptive. A collapse
+    // within one step is tangent blow-up.
+    let result = match spectrum(mode) {
+        Err(SpectrumError::Collapsed) => spectrum(match mode {
+            RenormMode::Fixed(_) => RenormMode::Adaptive {
+                ceiling: RETRY_CEILING,
+            },
+            RenormMode::Adaptive { .. } => RenormMode::Fixed(1),
+        }),
+        r => r,
+    };
+    match result {
+        Err(_) => Dynamics::Divergent {
+            at_step: p.transient + n,
+        },
+        Ok(data) => {
+            if torus(&data.spectrum, &data.convergence) {
+                Dynamics::Torus
+            } else if let Some(d) = chaotic(data, chaos_thresh(p, rate)) {
+                d
+            } else {
+                let traj = ode::integrate_traj(c, &s, dt, n);
+                let period = analysis::estimate_period(&traj, dt);
+                Dynamics::Cycle { period }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::lorenz_coeffs;
+
+    #[test]
+    fn lorenz_is_chaotic() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        match classify(&c) {
+            Dynamics::Chaotic(d) => {
+                assert!(d.spectrum[0] > 0.3);
+            }
+            other => {
+                panic!("expected chaotic, got {other:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn damped_is_fixed_point() {
+        // dx/dt = -x, dy/dt = -y, dz/dt = -z
+        let mut c = [0.0; 30];
+        c[1] = -1.0;    // eq0: -x
+        c[12] = -1.0;   // eq1: -y
+        c[23] = -1.0;   // eq2: -z
+        match classify(&c) {
+            Dynamics::FixedPoint => {}
+            other => {
+                panic!(
+                    "expected fixed point, got {other:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn divergence_step_reported() {
+        // x' = 50x from x = 0.1 passes r² = 1e6
+        // near t = 0.18, i.e. step ~36.
+        let mut c = [0.0; 30];
+        c[1] = 50.0;
+        match classify(&c) {
+            Dynamics::Divergent { at_step } => {
+                assert!((30..40).contains(&at_step), "{at_step}")
+            }
+            other => panic!("expected divergent, got {other:?}"),
+        }
+        c[1] = 0.5;
+        match classify(&c) {
+            Dynamics::Divergent { at_step } => {
+                assert!(at_step > 1000, "{at_step}")
+            }
+            other => panic!("expected divergent, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn adaptive_dt_rescues_fast_system() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0).map(|v| 100.0 * v);
+        let d = classify(&c);
+        assert!(matches!(d, Dynamics::Divergent { .. }), "{d:?}");
+        let p = ClassifyConfig {
+            adaptive_dt: true,
+            ..Default::default()
+        };
+        // λ2's absolute hyperchaos floor doesn't
+        // scale with time, so accept either.
+        match classify_with(&c, &p) {
+            Dynamics::Chaotic(d) | Dynamics::Hyperchaotic(d) => {
+                assert!(d.spectrum[0] > 50.0, "{d:?}")
+            }
+            other => panic!("expected chaos, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn settled_orbit_skips_to_fixed_point() {
+        let mut c = [0.0; 30];
+        c[1] = -1.0;
+        c[12] = -2.0;
+        c[23] = -1.0;
+        // Variance test off: only the pre-screen
+        // can report a fixed point.
+        let p = ClassifyConfig { fp_var: 0.0, ..Default::default() };
+        let d = classify_with(&c, &p);
+        assert!(matches!(d, Dynamics::FixedPoint), "{d:?}");
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let d = classify_with(&lor, &p);
+        assert!(matches!(d, Dynamics::Chaotic(_)), "{d:?}");
+    }
+
+    #[test]
+    fn start_state_selects_basin() {
+        // x' = x - x²: x = 1 attracts x > 0 only.
+        let mut c = [0.0; 30];
+        c[1] = 1.0;
+        c[4] = -1.0;
+        c[12] = -1.0;
+        c[23] = -1.0;
+        let at = |x| classify_from(&c, &[x, 0.1, 0.1]);
+        assert!(matches!(at(0.1), Dynamics::FixedPoint));
+        assert!(matches!(at(-0.1), Dynamics::Divergent { .. }));
+        assert!(matches!(classify(&c), Dynamics::FixedPoint));
+    }
+
+    #[test]
+    fn robust_escapes_bad_start() {
+        // u' = -u - u² with u = x - 1.5: stable at
+        // x = 1.5, divergent for x < 0.5, so S0 escapes.
+        let a = 1.5;
+        let mut c = [0.0; 30];
+        c[0] = a - a * a;
+        c[1] = -1.0 + 2.0 * a;
+        c[4] = -1.0;
+        c[12] = -1.0;
+        c[23] = -1.0;
+        assert!(matches!(classify(&c), Dynamics::Divergent { .. }));
+        let r = classify_robust(&c, 8);
+        assert!(matches!(r, Dynamics::FixedPoint), "{r:?}");
+        assert!(matches!(classify_robust(&c, 1), Dynamics::Divergent { .. }));
+    }
+
+    #[test]
+    fn calibrated_thresh_rescale() {
+        // Lorenz-like λ1 and rate, then slowed 200x.
+        let (l, r, k) = (0.9, 5.0, 0.005);
+        let fixed = ClassifyConfig::default();
+        let cal = ClassifyConfig {
+            calibrate: true,
+            ..Default::default()
+        };
+        let hit = |p: &ClassifyConfig, l, r| {
+            l > chaos_thresh(p, r)
+        };
+        assert!(hit(&fixed, l, r));
+        assert!(!hit(&fixed, l * k, r * k));
+        assert!(hit(&cal, l, r));
+        assert!(hit(&cal, l * k, r * k));
+    }
+
+    #[test]
+    fn chaos_thresh_is_configurable() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let p = ClassifyConfig {
+            chaos_thresh: 5.0,
+            lyap_steps: 10000,
+            ..Default::default()
+        };
+        let d = classify_with(&c, &p);
+        assert!(matches!(d, Dynamics::Cycle { .. }), "{d:?}");
+        let eq = ClassifyConfig { fp_var: 1e6, ..p };
+        let d = classify_with(&c, &eq);
+        assert!(matches!(d, Dynamics::FixedPoint), "{d:?}");
+    }
+
+    #[test]
+    fn torus_needs_two_zero_exponents() {
+        let torus = |s| torus(&s, &[0.0; 3]);
+        assert!(torus([0.002, -0.004, -1.0]));
+        assert!(!torus([0.001, -0.3, -1.0]));
+        // Conservative: nothing contracts.
+        assert!(!torus([0.001, 0.0, -0.001]));
+    }
+
+    #[test]
+    fn hyperchaos_needs_two_positive() {
+        let kind = |spectrum: [f64; 3]| {
+            let data = LyapData {
+                spectrum,
+                ky_dim: 0.0,
+                steps: 0,
+                unstable_dim: 0,
+                convergence: [0.0; 3],
+                divergence: 0.0,
+                lyapunov_time: None,
+            };
+            match chaotic(data, CHAOS_THRESH) {
+                Some(Dynamics::Hyperchaotic(_)) => 2,
+                Some(Dynamics::Chaotic(_)) => 1,
+                _ => 0,
+            }
+        };
+        assert_eq!(kind([0.9, 0.0, -14.5]), 1);
+        assert_eq!(kind([0.85, 0.013, -14.5]), 1);
+        assert_eq!(kind([0.3, 0.1, -2.0]), 2);
+        // Unsorted input is sorted first.
+        assert_eq!(kind([-2.0, 0.1, 0.3]), 2);
+        assert_eq!(kind([0.005, 0.0, -1.0]), 0);
+    }
+
+    #[test]
+    fn calibrated_classifies_rescaled_lorenz() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let cal = ClassifyConfig {
+            calibrate: true,
+            ..Default::default()
+        };
+        // Same physical time at every k, so only the
+        // threshold has to adapt.
+        let l1 = |k: f64| {
+            let n = (1.0 / k).max(1.0) as usize;
+            let p = ClassifyConfig {
+                transient: cal.transient * n,
+                lyap_steps: cal.lyap_steps * n,
+                ..cal.clone()
+            };
+            match classify_with(&c.map(|v| v * k), &p) {
+                Dynamics::Chaotic(d) => d.spectrum[0] / k,
+                d => panic!("k={k}: {d:?}"),
+            }
+        };
+        let base = l1(1.0);
+        for k in [0.1, 0.5, 2.0] {
+            let r = l1(k);
+            assert!((r - base).abs() < 0.1 * base, "k={k}: {r}");
+        }
+    }
+
+    #[test]
+    fn torus_band_follows_spread() {
+        // Lorenz-84's torus at (0.25, 4, 6.8, 1.36)
+        // at LYAP_STEPS: λ1 above CHAOS_THRESH, but
+        // within a few spreads of zero.
+        let spec = [0.027, -0.011, -0.84];
+        assert!(torus(&spec, &[0.0068, 0.0085, 0.0081]));
+        assert!(!torus(&spec, &[0.001, 0.001, 0.001]));
+        // Sprott I: weak chaos, λ1 ~ 7 spreads.
+        assert!(!torus(&[0.031, -0.012, -1.02], &[0.0046; 3]));
+        // λ2 must be near zero too.
+        assert!(!torus(&[0.005, -0.2, -1.0], &[0.01; 3]));
+    }
+
+    #[test]
+    fn collapsed_tangents_not_divergent() {
+        // Renorm every 2 time units: Lorenz's λ3
+        // direction falls below rounding.
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let p = ClassifyConfig {
+            renorm: 400,
+            ..Default::default()
+        };
+        let d = classify_with(&c, &p);
+        assert!(matches!(d, Dynamics::Chaotic(_)), "{d:?}");
+    }
+}
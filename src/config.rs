@@ -0,0 +1,92 @@
+// Run configuration file: a whole search as one
+// shareable JSON document. CLI flags override it.
+
+use crate::search::SearchOpts;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A search run. Omitted fields take defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+    /// "random" or "evolve".
+    pub mode: String,
+    /// Candidates for random search.
+    pub count: usize,
+    pub generations: usize,
+    pub pop: usize,
+    /// Catalog directory finds are saved to.
+    pub results: PathBuf,
+    pub search: SearchOpts,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            mode: "random".into(),
+            count: 10000,
+            generations: 500,
+            pop: 200,
+            results: PathBuf::from("results"),
+            search: SearchOpts::default(),
+        }
+    }
+}
+
+/// Read a run config from a JSON file.
+pub fn load(path: &Path) -> std::io::Result<RunConfig> {
+    let data = std::fs::read_to_string(path)?;
+    serde_json::from_str(&data).map_err(std::io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Agg, Verbosity};
+
+    #[test]
+    fn parse_full_config() {
+        let json = r#"{
+            "mode": "evolve", "count": 5, "generations": 7,
+            "pop": 9, "results": "out",
+            "search": {
+                "canonical": true,
+                "classify": {
+                    "calibrate": true,
+                    "early_stop": {
+                        "min_steps": 100, "margin": 0.1,
+                        "patience": 3
+                    }
+                },
+                "fitness": {
+                    "starts": 4, "agg": "median",
+                    "sparsity_penalty": 0.01
+                },
+                "mutate": { "start": 0.5, "end": 0.05 },
+                "band": { "min_dim": 2.1, "unstable_dim": 1 },
+                "verbosity": "verbose",
+                "chunk_size": 8,
+                "write_interval_ms": 250
+            }
+        }"#;
+        let c: RunConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(c.mode, "evolve");
+        assert_eq!((c.count, c.generations, c.pop), (5, 7, 9));
+        assert_eq!(c.results, PathBuf::from("out"));
+        let s = &c.search;
+        assert!(s.canonical && s.classify.calibrate);
+        let e = s.classify.early_stop.unwrap();
+        assert_eq!((e.min_steps, e.margin, e.patience), (100, 0.1, 3));
+        assert_eq!(s.fitness.starts, 4);
+        assert_eq!(s.fitness.agg, Agg::Median);
+        assert_eq!(s.fitness.sparsity_penalty, 0.01);
+        assert_eq!((s.mutate.start, s.mutate.end), (0.5, 0.05));
+        assert_eq!(s.band.min_dim, Some(2.1));
+        assert_eq!(s.band.max_dim, None);
+        assert_eq!(s.band.unstable_dim, Some(1));
+        assert_eq!(s.verbosity, Verbosity::Verbose);
+        assert_eq!((s.chunk_size, s.write_interval_ms), (8, 250));
+        let d: RunConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!((d.count, d.search.chunk_size), (10000, 1));
+    }
+}
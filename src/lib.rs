@@ -0,0 +1,18 @@
+// Attractor search: find novel strange attractors
+// in 3D quadratic polynomial ODE systems.
+
+pub mod analysis;
+pub mod catalog;
+pub mod classify;
+pub mod config;
+pub mod lyapunov;
+pub mod novelty;
+pub mod observer;
+pub mod ode;
+pub mod oden;
+pub mod ops;
+pub mod presets;
+pub mod render;
+pub mod search;
+pub mod sprott;
+pub mod system;
@@ -1,353 +1,918 @@
-// Lyapunov exponent computation via tangent-space
-// integration + QR (Gram-Schmidt) reorthogonalization.
-
-use crate::ode::{
-    self, Coeffs, State,
-};
-
-/// Data from Lyapunov computation.
-#[derive(Debug, Clone)]
-pub struct LyapData {
-    pub spectrum: [f64; 3],
-    pub ky_dim: f64,
-}
-
-/// 3-vector ops (inline, no alloc).
-type V3 = [f64; 3];
-
-#[inline(always)]
-fn dot(a: &V3, b: &V3) -> f64 {
-    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
-}
-
-#[inline(always)]
-fn norm(a: &V3) -> f64 {
-    dot(a, a).sqrt()
-}
-
-#[inline(always)]
-fn scale(a: &V3, s: f64) -> V3 {
-    [a[0]*s, a[1]*s, a[2]*s]
-}
-
-#[inline(always)]
-fn sub(a: &V3, b: &V3) -> V3 {
-    [a[0]-b[0], a[1]-b[1], a[2]-b[2]]
-}
-
-#[inline(always)]
-fn add(a: &V3, b: &V3) -> V3 {
-    [a[0]+b[0], a[1]+b[1], a[2]+b[2]]
-}
-
-/// Advance tangent vector w by one RK4 step.
-/// dw/dt = J(s) * w, where J is Jacobian at s.
-/// We need J at intermediate RK4 points too,
-/// so we co-integrate state and tangent.
-#[inline(always)]
-fn mat_vec(m: &[[f64; 3]; 3], v: &V3) -> V3 {
-    [
-        m[0][0]*v[0] + m[0][1]*v[1] + m[0][2]*v[2],
-        m[1][0]*v[0] + m[1][1]*v[1] + m[1][2]*v[2],
-        m[2][0]*v[0] + m[2][1]*v[1] + m[2][2]*v[2],
-    ]
-}
-
-/// RK4 co-integration of state + 3 tangent vectors.
-/// Returns (new_state, new_tangent_vecs).
-#[inline]
-fn rk4_tangent(
-    c: &Coeffs,
-    s: &State,
-    w: &[V3; 3],
-    dt: f64,
-) -> (State, [V3; 3]) {
-    // State k1
-    let f1 = ode::rhs(c, s);
-    let j1 = ode::jacobian(c, s);
-    let g1: [V3; 3] = [
-        mat_vec(&j1, &w[0]),
-        mat_vec(&j1, &w[1]),
-        mat_vec(&j1, &w[2]),
-    ];
-
-    // Midpoint 1
-    let s2 = [
-        s[0] + 0.5*dt*f1[0],
-        s[1] + 0.5*dt*f1[1],
-        s[2] + 0.5*dt*f1[2],
-    ];
-    let w2 = [
-        add(&w[0], &scale(&g1[0], 0.5*dt)),
-        add(&w[1], &scale(&g1[1], 0.5*dt)),
-        add(&w[2], &scale(&g1[2], 0.5*dt)),
-    ];
-    let f2 = ode::rhs(c, &s2);
-    let j2 = ode::jacobian(c, &s2);
-    let g2: [V3; 3] = [
-        mat_vec(&j2, &w2[0]),
-        mat_vec(&j2, &w2[1]),
-        mat_vec(&j2, &w2[2]),
-    ];
-
-    // Midpoint 2
-    let s3 = [
-        s[0] + 0.5*dt*f2[0],
-        s[1] + 0.5*dt*f2[1],
-        s[2] + 0.5*dt*f2[2],
-    ];
-    let w3 = [
-        add(&w[0], &scale(&g2[0], 0.5*dt)),
-        add(&w[1], &scale(&g2[1], 0.5*dt)),
-        add(&w[2], &scale(&g2[2], 0.5*dt)),
-    ];
-    let f3 = ode::rhs(c, &s3);
-    let j3 = ode::jacobian(c, &s3);
-    let g3: [V3; 3] = [
-        mat_vec(&j3, &w3[0]),
-        mat_vec(&j3, &w3[1]),
-        mat_vec(&j3, &w3[2]),
-    ];
-
-    // Endpoint
-    let s4 = [
-        s[0] + dt*f3[0],
-        s[1] + dt*f3[1],
-        s[2] + dt*f3[2],
-    ];
-    let w4 = [
-        add(&w[0], &scale(&g3[0], dt)),
-        add(&w[1], &scale(&g3[1], dt)),
-        add(&w[2], &scale(&g3[2], dt)),
-    ];
-    let f4 = ode::rhs(c, &s4);
-    let j4 = ode::jacobian(c, &s4);
-    let g4: [V3; 3] = [
-        mat_vec(&j4, &w4[0]),
-        mat_vec(&j4, &w4[1]),
-        mat_vec(&j4, &w4[2]),
-    ];
-
-    // Combine
-    let d6 = dt / 6.0;
-    let sn = [
-        s[0] + d6*(f1[0]+2.0*f2[0]+2.0*f3[0]+f4[0]),
-        s[1] + d6*(f1[1]+2.0*f2[1]+2.0*f3[1]+f4[1]),
-        s[2] + d6*(f1[2]+2.0*f2[2]+2.0*f3[2]+f4[2]),
-    ];
-    let mut wn = [[0.0; 3]; 3];
-    for i in 0..3 {
-        for j in 0..3 {
-            wn[i][j] = w[i][j] + d6 * (
-                g1[i][j]
-                + 2.0*g2[i][j]
-                + 2.0*g3[i][j]
-                + g4[i][j]
-            );
-        }
-    }
-    (sn, wn)
-}
-
-/// Gram-Schmidt orthonormalization of 3 vectors.
-/// Returns norms before normalization (for exponents).
-#[inline]
-fn gram_schmidt(w: &mut [V3; 3]) -> [f64; 3] {
-    // v0
-    let n0 = norm(&w[0]);
-    if n0 > 0.0 {
-        w[0] = scale(&w[0], 1.0 / n0);
-    }
-    // v1 -= (v1·v0)*v0
-    let d10 = dot(&w[1], &w[0]);
-    w[1] = sub(&w[1], &scale(&w[0], d10));
-    let n1 = norm(&w[1]);
-    if n1 > 0.0 {
-        w[1] = scale(&w[1], 1.0 / n1);
-    }
-    // v2 -= (v2·v0)*v0 + (v2·v1)*v1
-    let d20 = dot(&w[2], &w[0]);
-    let d21 = dot(&w[2], &w[1]);
-    w[2] = sub(
-        &sub(&w[2], &scale(&w[0], d20)),
-        &scale(&w[1], d21),
-    );
-    let n2 = norm(&w[2]);
-    if n2 > 0.0 {
-        w[2] = scale(&w[2], 1.0 / n2);
-    }
-    [n0, n1, n2]
-}
-
-/// Compute maximal Lyapunov exponent only (fast).
-pub fn max_lyapunov(
-    c: &Coeffs,
-    s0: &State,
-    dt: f64,
-    transient: usize,
-    steps: usize,
-) -> (f64, bool) {
-    let mut s = *s0;
-    // Transient
-    for _ in 0..transient {
-        s = ode::rk4_step(c, &s, dt);
-        if s[0]*s[0]+s[1]*s[1]+s[2]*s[2] > 1e8 {
-            return (f64::INFINITY, false);
-        }
-    }
-    let mut w: V3 = [1.0, 0.0, 0.0];
-    let mut sum = 0.0;
-    let bound = 1e6;
-    for _ in 0..steps {
-        // Advance state
-        let sn = ode::rk4_step(c, &s, dt);
-        // Advance tangent: dw/dt = J*w
-        let j = ode::jacobian(c, &s);
-        // Simple Euler for tangent (good enough for
-        // max exponent with frequent renorm)
-        let dw = mat_vec(&j, &w);
-        w = [
-            w[0] + dt * dw[0],
-            w[1] + dt * dw[1],
-            w[2] + dt * dw[2],
-        ];
-        s = sn;
-        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
-        if r2 > bound {
-            return (f64::INFINITY, false);
-        }
-        // Renormalize every step
-        let n = norm(&w);
-        if n > 0.0 && n.is_finite() {
-            sum += n.ln();
-            w = scale(&w, 1.0 / n);
-        } else {
-            return (f64::NAN, false);
-        }
-    }
-    let lyap = sum / (steps as f64 * dt);
-    (lyap, true)
-}
-
-/// Full Lyapunov spectrum (3 exponents) via QR.
-pub fn full_spectrum(
-    c: &Coeffs,
-    s0: &State,
-    dt: f64,
-    transient: usize,
-    steps: usize,
-    renorm_interval: usize,
-) -> Option<LyapData> {
-    let mut s = *s0;
-    for _ in 0..transient {
-        s = ode::rk4_step(c, &s, dt);
-        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
-        if r2 > 1e8 {
-            return None;
-        }
-    }
-    let mut w: [V3; 3] = [
-        [1.0, 0.0, 0.0],
-        [0.0, 1.0, 0.0],
-        [0.0, 0.0, 1.0],
-    ];
-    let mut sums = [0.0f64; 3];
-    let mut count = 0u64;
-    let bound = 1e6;
-    for step in 0..steps {
-        let (sn, wn) = rk4_tangent(c, &s, &w, dt);
-        s = sn;
-        w = wn;
-        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
-        if r2 > bound || !r2.is_finite() {
-            return None;
-        }
-        if (step + 1) % renorm_interval == 0 {
-            let norms = gram_schmidt(&mut w);
-            for i in 0..3 {
-                if norms[i] > 0.0
-                    && norms[i].is_finite()
-                {
-                    sums[i] += norms[i].ln();
-                } else {
-                    return None;
-                }
-            }
-            count += 1;
-        }
-    }
-    if count == 0 {
-        return None;
-    }
-    let t = count as f64
-        * renorm_interval as f64
-        * dt;
-    let spectrum = [
-        sums[0] / t,
-        sums[1] / t,
-        sums[2] / t,
-    ];
-    let ky = kaplan_yorke(&spectrum);
-    Some(LyapData { spectrum, ky_dim: ky })
-}
-
-/// Kaplan-Yorke dimension from sorted spectrum.
-fn kaplan_yorke(spec: &[f64; 3]) -> f64 {
-    let mut sorted = *spec;
-    sorted.sort_by(|a, b| {
-        b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal)
-    });
-    let mut sum = 0.0;
-    for (j, &l) in sorted.iter().enumerate() {
-        sum += l;
-        if sum < 0.0 {
-            if l.abs() < 1e-12 {
-                return j as f64;
-            }
-            return j as f64
-                + (sum - l) / l.abs();
-        }
-    }
-    3.0
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ode::lorenz_coeffs;
-
-    #[test]
-    fn lorenz_max_lyapunov() {
-        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
-        let s0: State = [1.0, 1.0, 1.0];
-        let (l, ok) = max_lyapunov(
-            &c, &s0, 0.005, 2000, 20000,
-        );
-        assert!(ok, "integration failed");
-        // Lorenz max LE ~ 0.9, accept 0.5..1.5
-        assert!(
-            l > 0.5 && l < 1.5,
-            "Lorenz LE={l}, expected ~0.9"
-        );
-    }
-
-    #[test]
-    fn lorenz_full_spectrum() {
-        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
-        let s0: State = [1.0, 1.0, 1.0];
-        let data = full_spectrum(
-            &c, &s0, 0.005, 2000, 40000, 10,
-        );
-        let d = data.expect("spectrum failed");
-        // λ1 > 0, λ2 ≈ 0, λ3 < 0
-        assert!(
-            d.spectrum[0] > 0.3,
-            "λ1={} too small", d.spectrum[0]
-        );
-        // KY dimension ~ 2.06
-        assert!(
-            d.ky_dim > 1.5 && d.ky_dim < 2.8,
-            "KY dim={}", d.ky_dim
-        );
-    }
-}
+// Lyapunov exponent computation via tangent-space
+// integration + QR (Gram-Schmidt) reorthogonalization.
+
+use crate::ode::{
+    self, Coeffs, State,
+};
+use crate::analysis;
+use serde::{Deserialize, Serialize};
+
+/// Data from Lyapunov computation.
+#[derive(Debug, Clone)]
+pub struct LyapData {
+    pub spectrum: [f64; 3],
+    pub ky_dim: f64,
+    /// Steps integrated (short on early stop).
+    pub steps: usize,
+    /// Exponents above UNSTABLE_MARGIN.
+    pub unstable_dim: usize,
+    /// Std of the running estimates over the last
+    /// quarter of renorms; large means unsettled.
+    pub convergence: [f64; 3],
+    /// Mean Jacobian trace (flow divergence) along
+    /// the orbit; the spectrum should sum to it.
+    pub divergence: f64,
+    /// 1/λ1 when λ1 > 0: the e-folding time of
+    /// nearby separations.
+    pub lyapunov_time: Option<f64>,
+}
+
+/// Exponents must exceed this to count as
+/// unstable (the flow direction sits near 0).
+pub const UNSTABLE_MARGIN: f64 = 0.05;
+
+/// Unstable manifold dimension: positive exponents.
+pub fn unstable_dim(spec: &[f64; 3]) -> usize {
+    spec.iter().filter(|&&l| l > UNSTABLE_MARGIN).count()
+}
+
+/// Abort rule for clearly non-chaotic spectra.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EarlyStop {
+    /// Steps before the rule may fire.
+    pub min_steps: usize,
+    /// Running λ1 must stay below -margin ...
+    pub margin: f64,
+    /// ... for this many consecutive renorms.
+    pub patience: usize,
+}
+
+impl Default for EarlyStop {
+    fn default() -> Self {
+        EarlyStop {
+            min_steps: 5000,
+            margin: 0.05,
+            patience: 20,
+        }
+    }
+}
+
+/// Why a spectrum run produced no estimate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumError {
+    /// The orbit or its tangents blew up.
+    Diverged,
+    /// The tangent vectors went numerically
+    /// parallel between renorms: the orbit may be
+    /// bounded, but the interval is too long.
+    Collapsed,
+}
+
+/// When the spectrum's tangent vectors are
+/// reorthonormalized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenormMode {
+    /// Every n steps (0 means 1).
+    Fixed(usize),
+    /// When the longest tangent vector passes
+    /// `ceiling` or the frame's volume falls below
+    /// 1/ceiling of the product of its lengths, so
+    /// fast systems renorm often and slow ones
+    /// rarely.
+    Adaptive { ceiling: f64 },
+}
+
+impl From<usize> for RenormMode {
+    fn from(n: usize) -> Self {
+        RenormMode::Fixed(n)
+    }
+}
+
+impl RenormMode {
+    /// Whether to renorm, `since` steps after the
+    /// last one.
+    fn due(&self, since: usize, w: &[V3; 3]) -> bool {
+        match *self {
+            RenormMode::Fixed(n) => since >= n.max(1),
+            RenormMode::Adaptive { ceiling } => {
+                let n = w.map(|v| norm(&v));
+                let vol = ode::det3(w).abs();
+                n.iter().any(|&l| l > ceiling)
+                    || vol * ceiling < n[0] * n[1] * n[2]
+            }
+        }
+    }
+}
+
+/// 3-vector ops (inline, no alloc), shared by
+/// analysis and render.
+pub(crate) type V3 = [f64; 3];
+
+#[inline(always)]
+pub(crate) fn dot(a: &V3, b: &V3) -> f64 {
+    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+#[inline(always)]
+pub(crate) fn norm(a: &V3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[inline(always)]
+pub(crate) fn scale(a: &V3, s: f64) -> V3 {
+    [a[0]*s, a[1]*s, a[2]*s]
+}
+
+#[inline(always)]
+pub(crate) fn sub(a: &V3, b: &V3) -> V3 {
+    [a[0]-b[0], a[1]-b[1], a[2]-b[2]]
+}
+
+#[inline(always)]
+pub(crate) fn add(a: &V3, b: &V3) -> V3 {
+    [a[0]+b[0], a[1]+b[1], a[2]+b[2]]
+}
+
+#[inline(always)]
+pub(crate) fn cross(a: &V3, b: &V3) -> V3 {
+    [
+        a[1]*b[2] - a[2]*b[1],
+        a[2]*b[0] - a[0]*b[2],
+        a[0]*b[1] - a[1]*b[0],
+    ]
+}
+
+/// Advance tangent vector w by one RK4 step.
+/// dw/dt = J(s) * w, where J is Jacobian at s.
+/// We need J at intermediate RK4 points too,
+/// so we co-integrate state and tangent.
+#[inline(always)]
+fn mat_vec(m: &[[f64; 3]; 3], v: &V3) -> V3 {
+    [
+        m[0][0]*v[0] + m[0][1]*v[1] + m[0][2]*v[2],
+        m[1][0]*v[0] + m[1][1]*v[1] + m[1][2]*v[2],
+        m[2][0]*v[0] + m[2][1]*v[1] + m[2][2]*v[2],
+    ]
+}
+
+/// RK4 co-integration of state + N tangent vectors.
+/// Returns (new_state, new_tangent_vecs).
+#[inline]
+fn rk4_tangent<const N: usize>(
+    c: &Coeffs,
+    s: &State,
+    w: &[V3; N],
+    dt: f64,
+) -> (State, [V3; N]) {
+    // Derivatives of state and tangents at (p, v).
+    let stage = |p: &State, v: &[V3; N]| {
+        let j = ode::jacobian(c, p);
+        (ode::rhs(c, p), v.map(|x| mat_vec(&j, &x)))
+    };
+    let step = |h: f64, f: &V3, g: &[V3; N]| {
+        let v: [V3; N] =
+            std::array::from_fn(|i| add(&w[i], &scale(&g[i], h)));
+        (add(s, &scale(f, h)), v)
+    };
+    let (f1, g1) = stage(s, w);
+    let (s2, w2) = step(0.5 * dt, &f1, &g1);
+    let (f2, g2) = stage(&s2, &w2);
+    let (s3, w3) = step(0.5 * dt, &f2, &g2);
+    let (f3, g3) = stage(&s3, &w3);
+    let (s4, w4) = step(dt, &f3, &g3);
+    let (f4, g4) = stage(&s4, &w4);
+
+    // Combine
+    let d6 = dt / 6.0;
+    let mix = |a: &V3, b: &V3, c: &V3, d: &V3| {
+        [0, 1, 2].map(|k| d6 * (a[k] + 2.0*b[k] + 2.0*c[k] + d[k]))
+    };
+    let sn = add(s, &mix(&f1, &f2, &f3, &f4));
+    let wn = std::array::from_fn(|i| {
+        add(&w[i], &mix(&g1[i], &g2[i], &g3[i], &g4[i]))
+    });
+    (sn, wn)
+}
+
+/// Residual/original norm below which a second
+/// projection pass runs ("twice is enough").
+const REORTH_RATIO: f64 = 0.5;
+/// Residual/original norm below which the vector is
+/// lost to rounding: the stretch would be noise.
+const GS_MIN_RATIO: f64 = 1e-13;
+
+/// Gram-Schmidt orthonormalization of 3 vectors.
+/// Returns norms before normalization (for
+/// exponents); None once a residual falls below
+/// GS_MIN_RATIO of its vector, i.e. the tangent
+/// vectors went numerically parallel.
+#[inline]
+fn gram_schmidt(w: &mut [V3; 3]) -> Option<[f64; 3]> {
+    let mut norms = [0.0; 3];
+    for i in 0..3 {
+        let orig = norm(&w[i]);
+        let mut v = w[i];
+        let mut n = orig;
+        for pass in 0..2 {
+            for u in &w[..i] {
+                v = sub(&v, &scale(u, dot(&v, u)));
+            }
+            n = norm(&v);
+            if pass == 1 || n >= REORTH_RATIO * orig {
+                break;
+            }
+        }
+        if n.is_nan() || n <= GS_MIN_RATIO * orig {
+            return None;
+        }
+        w[i] = scale(&v, 1.0 / n);
+        norms[i] = n;
+    }
+    Some(norms)
+}
+
+/// Reorthonormalize w, adding log stretches to
+/// sums.
+#[inline]
+fn accumulate(
+    w: &mut [V3; 3],
+    sums: &mut [f64; 3],
+) -> Result<(), SpectrumError> {
+    if !w.iter().flatten().all(|v| v.is_finite()) {
+        return Err(SpectrumError::Diverged);
+    }
+    let norms = gram_schmidt(w).ok_or(SpectrumError::Collapsed)?;
+    for (sum, n) in sums.iter_mut().zip(norms) {
+        *sum += n.ln();
+    }
+    Ok(())
+}
+
+/// Maximal Lyapunov exponent only (fast): one
+/// RK4-integrated tangent vector, renormalized
+/// every `renorm_interval` steps (0 means 1).
+pub fn max_lyapunov(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    transient: usize,
+    steps: usize,
+    renorm_interval: usize,
+) -> (f64, bool) {
+    let mut s = *s0;
+    // Transient
+    for _ in 0..transient {
+        s = ode::rk4_step(c, &s, dt);
+        if s[0]*s[0]+s[1]*s[1]+s[2]*s[2] > 1e8 {
+            return (f64::INFINITY, false);
+        }
+    }
+    let every = renorm_interval.max(1);
+    let mut w: [V3; 1] = [[1.0, 0.0, 0.0]];
+    let mut sum = 0.0;
+    let bound = 1e6;
+    for step in 1..=steps {
+        (s, w) = rk4_tangent(c, &s, &w, dt);
+        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
+        if r2 > bound {
+            return (f64::INFINITY, false);
+        }
+        if step % every == 0 || step == steps {
+            let n = norm(&w[0]);
+            if n > 0.0 && n.is_finite() {
+                sum += n.ln();
+                w[0] = scale(&w[0], 1.0 / n);
+            } else {
+                return (f64::NAN, false);
+            }
+        }
+    }
+    let lyap = sum / (steps as f64 * dt);
+    (lyap, true)
+}
+
+/// Maximal exponent by the two-trajectory method:
+/// no Jacobian, just `rk4_step` on a reference and
+/// a copy displaced by d0, rescaled back to d0
+/// every `renorm` steps.
+pub fn max_lyapunov_separation(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    d0: f64,
+    transient: usize,
+    steps: usize,
+    renorm: usize,
+) -> (f64, bool) {
+    let mut s = *s0;
+    for _ in 0..transient {
+        s = ode::rk4_step(c, &s, dt);
+        if dot(&s, &s) > 1e8 {
+            return (f64::INFINITY, false);
+        }
+    }
+    let every = renorm.max(1);
+    let mut p = add(&s, &[d0, 0.0, 0.0]);
+    let mut sum = 0.0;
+    for step in 1..=steps {
+        s = ode::rk4_step(c, &s, dt);
+        p = ode::rk4_step(c, &p, dt);
+        if dot(&s, &s) > 1e6 {
+            return (f64::INFINITY, false);
+        }
+        if step % every == 0 || step == steps {
+            let d = sub(&p, &s);
+            let n = norm(&d);
+            if !(n > 0.0 && n.is_finite()) {
+                return (f64::NAN, false);
+            }
+            sum += (n / d0).ln();
+            p = add(&s, &scale(&d, d0 / n));
+        }
+    }
+    (sum / (steps as f64 * dt), true)
+}
+
+/// Finite-time local maximal exponents: the tangent
+/// vector (initially w0) is RK4 co-integrated, so it
+/// settles on the unstable direction during the
+/// transient, and renormalized every
+/// `window` steps, giving one (point, exponent)
+/// pair per window, keyed by the window's start.
+/// Their mean is the global λ1 over the run.
+/// Empty if the orbit diverges.
+pub fn local_exponents(
+    c: &Coeffs,
+    s0: &State,
+    w0: &State,
+    dt: f64,
+    transient: usize,
+    windows: usize,
+    window: usize,
+) -> Vec<(State, f64)> {
+    let (mut s, window) = (*s0, window.max(1));
+    let mut w = [scale(w0, 1.0 / norm(w0))];
+    for _ in 0..transient {
+        (s, w) = rk4_tangent(c, &s, &w, dt);
+        let n = norm(&w[0]);
+        if !(n > 0.0 && n.is_finite()) {
+            return Vec::new();
+        }
+        w[0] = scale(&w[0], 1.0 / n);
+    }
+    let span = window as f64 * dt;
+    let mut out = Vec::with_capacity(windows);
+    for _ in 0..windows {
+        let start = s;
+        for _ in 0..window {
+            (s, w) = rk4_tangent(c, &s, &w, dt);
+        }
+        let n = norm(&w[0]);
+        if !(n > 0.0 && n.is_finite() && dot(&s, &s) < 1e6) {
+            return Vec::new();
+        }
+        w[0] = scale(&w[0], 1.0 / n);
+        out.push((start, n.ln() / span));
+    }
+    out
+}
+
+/// Largest finite-time exponent over each of
+/// n_windows consecutive windows of `window` steps
+/// from s0 (no transient): the values of
+/// local_exponents. Empty if the orbit diverges.
+pub fn ftle_distribution(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    window: usize,
+    n_windows: usize,
+) -> Vec<f64> {
+    let w0 = [1.0, 1.0, 1.0];
+    local_exponents(c, s0, &w0, dt, 0, n_windows, window)
+        .into_iter()
+        .map(|(_, l)| l)
+        .collect()
+}
+
+/// (mean, standard deviation) of FTLE values;
+/// None if empty.
+pub fn ftle_summary(v: &[f64]) -> Option<(f64, f64)> {
+    if v.is_empty() {
+        return None;
+    }
+    let n = v.len() as f64;
+    let mean = v.iter().sum::<f64>() / n;
+    let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    Some((mean, var.sqrt()))
+}
+
+/// Full Lyapunov spectrum (3 exponents) via QR;
+/// `renorm` is a RenormMode or a fixed interval.
+pub fn full_spectrum(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    transient: usize,
+    steps: usize,
+    renorm: impl Into<RenormMode>,
+) -> Option<LyapData> {
+    full_spectrum_with(c, s0, dt, transient, steps, renorm, None).ok()
+}
+
+/// Full spectrum, optionally stopping early once
+/// the running λ1 is firmly negative. Errors tell
+/// divergence from collapsed tangents.
+pub fn full_spectrum_with(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    transient: usize,
+    steps: usize,
+    renorm: impl Into<RenormMode>,
+    early: Option<&EarlyStop>,
+) -> Result<LyapData, SpectrumError> {
+    spectrum_run(c, s0, dt, transient, steps, renorm.into(), early)
+        .map(|r| r.0)
+}
+
+/// Running spectrum estimate at each renorm, for
+/// checking that exponents have plateaued. Empty
+/// if the orbit diverges.
+pub fn spectrum_history(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    transient: usize,
+    steps: usize,
+    renorm: impl Into<RenormMode>,
+) -> Vec<[f64; 3]> {
+    spectrum_run(c, s0, dt, transient, steps, renorm.into(), None)
+        .map(|r| r.1)
+        .unwrap_or_default()
+}
+
+/// The spectrum and its per-renorm history.
+fn spectrum_run(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    transient: usize,
+    steps: usize,
+    renorm: RenormMode,
+    early: Option<&EarlyStop>,
+) -> Result<(LyapData, Vec<[f64; 3]>), SpectrumError> {
+    let mut s = *s0;
+    for _ in 0..transient {
+        s = ode::rk4_step(c, &s, dt);
+        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
+        if r2 > 1e8 {
+            return Err(SpectrumError::Diverged);
+        }
+    }
+    let mut w: [V3; 3] = [
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+    let mut sums = [0.0f64; 3];
+    let bound = 1e6;
+    // Steps covered by the last renorm.
+    let mut last = 0;
+    let (mut below, mut done) = (0, steps);
+    let mut hist = Vec::new();
+    // The trace is linear in s: sum s, not J.
+    let mut s_sum = [0.0f64; 3];
+    for step in 0..steps {
+        s_sum = add(&s_sum, &s);
+        let (sn, wn) = rk4_tangent(c, &s, &w, dt);
+        s = sn;
+        w = wn;
+        let r2 = s[0]*s[0]+s[1]*s[1]+s[2]*s[2];
+        if r2 > bound || !r2.is_finite() {
+            return Err(SpectrumError::Diverged);
+        }
+        if renorm.due(step + 1 - last, &w) {
+            accumulate(&mut w, &mut sums)?;
+            last = step + 1;
+            let t = last as f64 * dt;
+            hist.push([sums[0] / t, sums[1] / t, sums[2] / t]);
+            if let Some(e) = early {
+                let l1 = sums[0] / (last as f64 * dt);
+                if last < e.min_steps || l1 >= -e.margin {
+                    below = 0;
+                } else {
+                    below += 1;
+                }
+                if below >= e.patience {
+                    done = last;
+                    break;
+                }
+            }
+        }
+    }
+    // Trailing partial interval: its stretching
+    // counts over its actual length.
+    if last < done {
+        accumulate(&mut w, &mut sums)?;
+        last = done;
+    }
+    if last == 0 {
+        return Err(SpectrumError::Diverged);
+    }
+    let t = last as f64 * dt;
+    let spectrum = [
+        sums[0] / t,
+        sums[1] / t,
+        sums[2] / t,
+    ];
+    let ky = kaplan_yorke(&spectrum, KY_TOL);
+    let data = LyapData {
+        spectrum,
+        ky_dim: ky,
+        steps: done,
+        unstable_dim: unstable_dim(&spectrum),
+        convergence: spread(&hist[hist.len() * 3 / 4..]),
+        divergence: ode::divergence(
+            c,
+            &scale(&s_sum, 1.0 / done as f64),
+        ),
+        lyapunov_time: (spectrum[0] > 0.0).then(|| 1.0 / spectrum[0]),
+    };
+    Ok((data, hist))
+}
+
+/// Per-exponent std of running estimates.
+fn spread(h: &[[f64; 3]]) -> [f64; 3] {
+    let n = h.len() as f64;
+    if h.len() < 2 {
+        return [0.0; 3];
+    }
+    let mut out = [0.0; 3];
+    for (k, o) in out.iter_mut().enumerate() {
+        let m = h.iter().map(|v| v[k]).sum::<f64>() / n;
+        let v = h.iter().map(|v| (v[k] - m).powi(2)).sum::<f64>();
+        *o = (v / (n - 1.0)).sqrt();
+    }
+    out
+}
+
+/// Largest exponent of a scalar series (Rosenstein
+/// et al. 1993): delay-embed, pair each point with
+/// its nearest neighbour outside a Theiler window of
+/// one mean period (from mean crossings), and fit
+/// the mean log separation over lags of 0.5 to 1.5
+/// mean periods. NaN if the series is too short.
+pub fn rosenstein(
+    series: &[f64],
+    dt: f64,
+    emb_dim: usize,
+    tau: usize,
+) -> f64 {
+    let m = emb_dim.max(1);
+    let n = series.len();
+    let span = (m - 1) * tau;
+    if n <= span + 2 {
+        return f64::NAN;
+    }
+    let emb = analysis::delay_embed(series, m, tau);
+    let pts = emb.len();
+    let mean = series.iter().sum::<f64>() / n as f64;
+    let cross = series
+        .windows(2)
+        .filter(|w| (w[0] - mean) * (w[1] - mean) < 0.0)
+        .count();
+    let w = 2 * n / cross.max(1);
+    let (lo, hi) = (w / 2, w + w / 2);
+    let d = |i: usize, j: usize| -> f64 {
+        let (a, b) = (&emb[i], &emb[j]);
+        a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt()
+    };
+    let mut sum = vec![0.0; hi + 1];
+    let mut cnt = vec![0usize; hi + 1];
+    for i in 0..pts {
+        let near = (0..pts)
+            .filter(|&j| i.abs_diff(j) > w)
+            .map(|j| (d(i, j), j))
+            .filter(|p| p.0 > 0.0)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        let Some((_, j)) = near else { continue };
+        for k in 0..=hi.min(pts - 1 - i.max(j)) {
+            let v = d(i + k, j + k);
+            if v > 0.0 {
+                sum[k] += v.ln();
+                cnt[k] += 1;
+            }
+        }
+    }
+    let curve: Vec<(f64, f64)> = (lo..=hi)
+        .filter(|&k| cnt[k] > 0)
+        .map(|k| (k as f64 * dt, sum[k] / cnt[k] as f64))
+        .collect();
+    if curve.len() < 2 {
+        return f64::NAN;
+    }
+    analysis::slope(&curve)
+}
+
+/// Default near-zero exponent tolerance for KY.
+pub const KY_TOL: f64 = 1e-3;
+
+/// Kaplan-Yorke dimension, continuous in the
+/// spectrum. Each exponent k adds
+/// clamp(1 + S_k / max(|λ_k|, tol), 0, 1), S_k the
+/// partial sum; this equals j + S_j/|λ_{j+1}| when
+/// no |λ| < tol, and ramps linearly instead of
+/// jumping when a marginal exponent crosses zero.
+pub fn kaplan_yorke(spec: &[f64; 3], tol: f64) -> f64 {
+    let mut sorted = *spec;
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let mut sum = 0.0;
+    let mut dim = 0.0;
+    for &l in sorted.iter() {
+        sum += l;
+        let d = l.abs().max(tol);
+        dim += (1.0 + sum / d).clamp(0.0, 1.0);
+    }
+    dim
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::lorenz_coeffs;
+
+    #[test]
+    fn lorenz_max_lyapunov() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let (l, ok) = max_lyapunov(
+            &c, &s0, 0.005, 2000, 20000, 10,
+        );
+        assert!(ok, "integration failed");
+        // Lorenz max LE ~ 0.9, accept 0.5..1.5
+        assert!(
+            l > 0.5 && l < 1.5,
+            "Lorenz LE={l}, expected ~0.9"
+        );
+    }
+
+    #[test]
+    fn separation_matches_tangent() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let (t, _) = max_lyapunov(&c, &s0, 0.005, 2000, 40000, 10);
+        let (l, ok) = max_lyapunov_separation(
+            &c, &s0, 0.005, 1e-8, 2000, 40000, 10,
+        );
+        assert!(ok, "integration failed");
+        assert!((l - t).abs() < 0.05, "sep={l} tangent={t}");
+        assert!((l - 0.9).abs() < 0.1, "sep={l}");
+    }
+
+    #[test]
+    fn local_exponents_average_to_global() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let w0 = [1.0, 1.0, 0.0];
+        let loc = local_exponents(&c, &s0, &w0, 0.005, 2000, 4000, 10);
+        assert_eq!(loc.len(), 4000);
+        assert!(loc.iter().all(|(_, l)| l.is_finite()));
+        let mean = loc.iter().map(|(_, l)| l).sum::<f64>()
+            / loc.len() as f64;
+        assert!((mean - 0.9).abs() < 0.1, "mean={mean}");
+        // Stretching is uneven over the attractor.
+        assert!(loc.iter().any(|(_, l)| *l < 0.0));
+    }
+
+    #[test]
+    fn lorenz_full_spectrum() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let data = full_spectrum(
+            &c, &s0, 0.005, 2000, 40000, 10,
+        );
+        let d = data.expect("spectrum failed");
+        // λ1 > 0, λ2 ≈ 0, λ3 < 0
+        assert!(
+            d.spectrum[0] > 0.3,
+            "λ1={} too small", d.spectrum[0]
+        );
+        // KY dimension ~ 2.06
+        assert!(
+            d.ky_dim > 1.5 && d.ky_dim < 2.8,
+            "KY dim={}", d.ky_dim
+        );
+    }
+
+    #[test]
+    fn rk4_max_beats_euler_tangent() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let (dt, n) = (0.005, 40000);
+        let l1 = full_spectrum(&c, &s0, dt, 2000, n, 10)
+            .expect("spectrum failed")
+            .spectrum[0];
+        // The Euler tangent max_lyapunov used to
+        // step, renormalized every step.
+        let mut s = ode::integrate(&c, &s0, dt, 2000);
+        let (mut w, mut sum): (V3, f64) = ([1.0, 0.0, 0.0], 0.0);
+        for _ in 0..n {
+            let dw = mat_vec(&ode::jacobian(&c, &s), &w);
+            s = ode::rk4_step(&c, &s, dt);
+            w = add(&w, &scale(&dw, dt));
+            sum += norm(&w).ln();
+            w = scale(&w, 1.0 / norm(&w));
+        }
+        let euler = sum / (n as f64 * dt);
+        let (m, ok) = max_lyapunov(&c, &s0, dt, 2000, n, 10);
+        assert!(ok);
+        assert!((m - l1).abs() < 0.03, "{m} vs λ1 {l1}");
+        assert!(
+            (m - l1).abs() * 3.0 < (euler - l1).abs(),
+            "rk4 {m}, euler {euler}, λ1 {l1}"
+        );
+    }
+
+    #[test]
+    fn unstable_dim_counts_positive() {
+        assert_eq!(unstable_dim(&[0.3, 0.1, -5.0]), 2);
+        assert_eq!(unstable_dim(&[0.9, 0.01, -14.5]), 1);
+        assert_eq!(unstable_dim(&[-0.1, -0.2, -3.0]), 0);
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let d = full_spectrum(
+            &c, &[1.0, 1.0, 1.0], 0.005, 2000, 20000, 10,
+        ).expect("spectrum failed");
+        assert_eq!(d.unstable_dim, 1);
+    }
+
+    #[test]
+    fn rosenstein_lorenz_x() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s = ode::integrate(&c, &[1.0, 1.0, 1.0], 0.01, 2000);
+        let x: Vec<f64> = ode::integrate_traj(&c, &s, 0.01, 4000)
+            .iter()
+            .map(|p| p[0])
+            .collect();
+        let l = rosenstein(&x, 0.01, 3, 10);
+        assert!((l - 0.9).abs() < 0.2, "λ={l}");
+        assert!(rosenstein(&x[..20], 0.01, 3, 10).is_nan());
+    }
+
+    #[test]
+    fn convergence_tightens_with_length() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let run = |n| {
+            full_spectrum(&c, &s0, 0.005, 2000, n, 10)
+                .unwrap()
+                .convergence[0]
+        };
+        let (short, long) = (run(4000), run(40000));
+        assert!(long > 0.0 && long < short, "{long} vs {short}");
+        assert!(long < 0.05, "std={long}");
+    }
+
+    #[test]
+    fn gram_schmidt_rejects_parallel_vectors() {
+        // Residuals of 1e-7: reorthogonalized to
+        // rounding, where one pass leaves ~1e-9.
+        let mut w = [
+            [1.0, 0.0, 0.0],
+            [1.0, 1e-7, 0.0],
+            [1.0, 1e-7, 1e-7],
+        ];
+        let n = gram_schmidt(&mut w).unwrap();
+        assert!((n[1] - 1e-7).abs() < 1e-15, "{n:?}");
+        for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+            assert!(dot(&w[i], &w[j]).abs() < 1e-15);
+        }
+        let mut w = [[1.0, 0.0, 0.0], [1.0, 1e-15, 0.0], [0.0, 0.0, 1.0]];
+        assert!(gram_schmidt(&mut w).is_none());
+        // Renorm every 4 time units: λ3's direction
+        // shrinks past rounding, which used to give
+        // λ3 ~ -7 instead of -14.5.
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        assert_eq!(
+            full_spectrum_with(&c, &s0, 0.005, 1000, 40000, 800, None)
+                .unwrap_err(),
+            SpectrumError::Collapsed,
+        );
+        assert!(full_spectrum(&c, &s0, 0.005, 1000, 40000, 100).is_some());
+    }
+
+    #[test]
+    fn adaptive_renorm_matches_fixed() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let fixed = full_spectrum(&c, &s0, 0.005, 1000, 40000, 10).unwrap();
+        let mode = RenormMode::Adaptive { ceiling: 100.0 };
+        let h = spectrum_history(&c, &s0, 0.005, 1000, 40000, mode);
+        // Fewer renorms than fixed(10)'s 4000.
+        assert!(h.len() > 100 && h.len() < 4000, "{}", h.len());
+        let ad = full_spectrum(&c, &s0, 0.005, 1000, 40000, mode).unwrap();
+        for (a, b) in ad.spectrum.iter().zip(fixed.spectrum) {
+            assert!((a - b).abs() < 0.01, "{:?}", ad.spectrum);
+        }
+    }
+
+    #[test]
+    fn history_ends_at_spectrum() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let h = spectrum_history(&c, &s0, 0.005, 1000, 5000, 10);
+        assert_eq!(h.len(), 500);
+        let d = full_spectrum(&c, &s0, 0.005, 1000, 5000, 10).unwrap();
+        assert_eq!(h[499], d.spectrum);
+        let mut blow = [0.0; crate::ode::NCOEFFS];
+        blow[1] = 50.0;
+        assert!(spectrum_history(&blow, &s0, 0.005, 0, 100, 10).is_empty());
+    }
+
+    #[test]
+    fn spectrum_sums_to_divergence() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let d = full_spectrum(&c, &[1.0; 3], 0.005, 2000, 20000, 10)
+            .unwrap();
+        let want = -(10.0 + 1.0 + 8.0 / 3.0);
+        assert!((d.divergence - want).abs() < 1e-9, "{}", d.divergence);
+        let sum: f64 = d.spectrum.iter().sum();
+        assert!((sum - d.divergence).abs() < 0.01, "sum={sum}");
+    }
+
+    #[test]
+    fn early_stop_negative_only() {
+        let e = EarlyStop::default();
+        let mut damp = [0.0; crate::ode::NCOEFFS];
+        damp[1] = -1.0;
+        damp[12] = -1.0;
+        damp[23] = -1.0;
+        let s0: State = [1.0, 1.0, 1.0];
+        let d = full_spectrum_with(
+            &damp, &s0, 0.005, 0, 30000, 10, Some(&e),
+        )
+        .expect("damped spectrum");
+        assert!(d.steps < 30000, "damped ran full");
+        assert!(d.spectrum[0] < 0.0);
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let d = full_spectrum_with(
+            &lor, &s0, 0.005, 2000, 30000, 10, Some(&e),
+        )
+        .expect("Lorenz spectrum");
+        assert_eq!(d.steps, 30000);
+    }
+
+    #[test]
+    fn ky_matches_formula() {
+        let d = kaplan_yorke(&[0.9, 0.0, -14.5], KY_TOL);
+        assert!((d - (2.0 + 0.9 / 14.5)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn ky_continuous_through_zero() {
+        // Sweep λ2 through zero with λ1 marginal.
+        let mut prev = None;
+        for i in -1000..=1000 {
+            let x = i as f64 * 1e-5;
+            let d = kaplan_yorke(&[0.0, x, -2.0], KY_TOL);
+            if let Some(p) = prev {
+                let jump: f64 = d - p;
+                assert!(jump.abs() < 0.02, "jump at {x}");
+            }
+            prev = Some(d);
+        }
+    }
+
+    #[test]
+    fn partial_interval_counted() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0/3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let run = |every| {
+            full_spectrum(&c, &s0, 0.005, 2000, 40000, every)
+                .expect("spectrum failed")
+                .spectrum
+        };
+        // 40000 % 17 != 0; 0 clamps to 1.
+        let (a, b, z) = (run(10), run(17), run(0));
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 0.05, "{a:?} {b:?}");
+            assert!((a[i] - z[i]).abs() < 0.05, "{a:?} {z:?}");
+        }
+        assert!((a[0] - 0.9).abs() < 0.1);
+    }
+
+    #[test]
+    fn ftle_mean_near_lambda1() {
+        let c = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = ode::integrate(&c, &[0.1; 3], 0.01, 2000);
+        let v = ftle_distribution(&c, &s0, 0.01, 100, 300);
+        assert_eq!(v.len(), 300);
+        let (mean, std) = ftle_summary(&v).unwrap();
+        assert!((0.7..1.1).contains(&mean), "mean {mean}");
+        // One-time-unit windows fluctuate strongly.
+        assert!(std > 0.3, "std {std}");
+        assert_eq!(ftle_summary(&[]), None);
+    }
+}
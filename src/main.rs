@@ -1,67 +1,809 @@
-// Attractor search: find novel strange attractors
-// in 3D quadratic polynomial ODE systems.
-
-mod catalog;
-mod classify;
-mod lyapunov;
-mod ode;
-mod search;
-
-use std::path::PathBuf;
-
-fn usage() {
-    eprintln!(
-        "Usage:\n  \
-         attractor-search random --count N\n  \
-         attractor-search evolve \
-         --generations G --pop P"
-    );
-}
-
-fn main() {
-    let args: Vec<String> =
-        std::env::args().collect();
-    if args.len() < 2 {
-        usage();
-        std::process::exit(1);
-    }
-
-    let results = PathBuf::from("results");
-
-    match args[1].as_str() {
-        "random" => {
-            let count = parse_flag(
-                &args, "--count",
-            )
-            .unwrap_or(10000);
-            search::random_search(count, &results);
-        }
-        "evolve" => {
-            let gens = parse_flag(
-                &args, "--generations",
-            )
-            .unwrap_or(500);
-            let pop = parse_flag(
-                &args, "--pop",
-            )
-            .unwrap_or(200);
-            search::evolve_search(
-                gens, pop, &results,
-            );
-        }
-        _ => {
-            usage();
-            std::process::exit(1);
-        }
-    }
-}
-
-fn parse_flag(
-    args: &[String],
-    flag: &str,
-) -> Option<usize> {
-    args.iter()
-        .position(|a| a == flag)
-        .and_then(|i| args.get(i + 1))
-        .and_then(|v| v.parse().ok())
-}
+// Attractor search: find novel strange attractors
+// in 3D quadratic polynomial ODE systems.
+
+use attractor_search::classify::{self, ClassifyConfig, Dynamics};
+use attractor_search::config::{self, RunConfig};
+use attractor_search::search::{
+    Band, FitnessOpts, MutateSchedule, SearchOpts,
+    SearchSummary, Verbosity,
+};
+use attractor_search::observer::StdoutObserver;
+use attractor_search::ops::CoeffMask;
+use attractor_search::render::{self, View};
+use attractor_search::{
+    analysis, catalog, lyapunov, ode, search, sprott, system,
+};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+fn usage() {
+    eprintln!(
+        "Usage:\n  \
+         attractor-search random --count N \
+         [--chunk-size C] [--sparsity K]\n  \
+         attractor-search evolve \
+         --generations G --pop P \
+         [--checkpoint-every N] [--resume] \
+         [--seed-from-catalog]\n  \
+         attractor-search islands --generations G \
+         --islands M --pop P [--migration-interval K]\n  \
+         attractor-search run --config FILE\n  \
+         attractor-search psd --id HASH \
+         [--coord x|y|z] [--out FILE]\n  \
+         attractor-search export --id HASH \
+         [--format csv|npy|ply|sprott] [--color-speed] \
+         [--sample time|arclength] [--points N] \
+         [--out FILE]\n  \
+         attractor-search export --catalog --format npy \
+         [--out FILE]\n  \
+         attractor-search import --format sprott CODE\n  \
+         attractor-search classify --coeffs c0,...,c29\n  \
+         attractor-search classify --system FILE.toml\n  \
+         attractor-search classify --from-id HASH \
+         [--dir DIR]\n  \
+         attractor-search diff DIR_A DIR_B\n  \
+         attractor-search merge SRC_DIR\n  \
+         attractor-search dedupe [--tol T]\n  \
+         attractor-search histogram [--bins N] \
+         [--out FILE]\n  \
+         attractor-search stats [--dir DIR]\n  \
+         attractor-search verify [--dir DIR]\n  \
+         attractor-search list [--min-lyap L] [--max-lyap L] \
+         [--min-dim D] [--max-dim D] [--method M] \
+         [--max-terms K] [--sort lambda1|ky_dim] [--limit N]\n  \
+         attractor-search render --id HASH \
+         [--size N] [--anaglyph] [--fold-symmetry] \
+         [--raw-pose] \
+         [--perspective [--camera x,y,z] [--fov DEG]] \
+         [--stretch-map] \
+         [--out FILE]\n\
+         Search flags: [--config FILE] [--seed N] \
+         [-q|-v|-vv] [--canonical] \
+         [--write-interval-ms MS] [--compress] [--fix-origin] \
+         [--store-points N] \
+         [--calibrate] [--classify-starts N] \
+         [--early-stop]\n  \
+         [--chaos-thresh L] [--fp-var V] [--div-thresh R2] \
+         [--classify-dt DT] [--adaptive-dt] [--transient N] \
+         [--classify-steps N] [--lyap-steps N] [--renorm N]\n  \
+         [--renorm-ceiling C]\n  \
+         [--min-dim D] [--max-dim D] \
+         [--min-lambda1 L] [--max-lambda1 L] \
+         [--unstable-dim K]\n  \
+         evolve: [--fitness-starts K] \
+         [--fitness-agg min|mean|median] \
+         [--sparsity-penalty W] \
+         [--novelty-weight W] [--novelty-k K] \
+         [--mutate-std-start S] [--mutate-std-end E] \
+         [--crossover-rate R] [--tournament-size K]"
+    );
+}
+
+fn main() {
+    let args: Vec<String> =
+        std::env::args().collect();
+    if args.len() < 2 {
+        usage();
+        std::process::exit(1);
+    }
+
+    let cfg = match flag_str(&args, "--config") {
+        Some(p) => config::load(Path::new(p))
+            .unwrap_or_else(|e| fail(&format!("{p}: {e}"))),
+        None => RunConfig::default(),
+    };
+    let results = cfg.results.clone();
+    let opts = search_opts(&args, cfg.search.clone());
+    let mut out = StdoutObserver::new(opts.verbosity);
+    let mode = match args[1].as_str() {
+        "run" => cfg.mode.as_str(),
+        m => m,
+    };
+
+    match mode {
+        "random" => {
+            let count = parse_flag(&args, "--count")
+                .unwrap_or(cfg.count);
+            let sum =
+                search::random_search(count, &results, &opts, &mut out);
+            print_summary(&sum);
+        }
+        "evolve" => {
+            let gens = parse_flag(&args, "--generations")
+                .unwrap_or(cfg.generations);
+            let pop = parse_flag(&args, "--pop")
+                .unwrap_or(cfg.pop);
+            let sum =
+                search::evolve_search(gens, pop, &results, &opts, &mut out)
+                    .unwrap_or_else(|e| fail(&format!("cannot resume: {e}")));
+            print_summary(&sum);
+        }
+        "islands" => {
+            let gens = parse_flag(&args, "--generations")
+                .unwrap_or(cfg.generations);
+            let islands = parse_flag(&args, "--islands").unwrap_or(4);
+            let pop = parse_flag(&args, "--pop").unwrap_or(cfg.pop);
+            let every =
+                parse_flag(&args, "--migration-interval").unwrap_or(10);
+            let sum = search::evolve_islands(
+                gens, islands, pop, every, &results, &opts, &mut out,
+            );
+            print_summary(&sum);
+        }
+        "psd" => run_psd(&args, &results),
+        "export" => run_export(&args, &results),
+        "diff" => run_diff(&args),
+        "merge" => {
+            let src = args.get(2).unwrap_or_else(|| fail("merge SRC_DIR"));
+            let m = catalog::merge(Path::new(src), &results)
+                .unwrap_or_else(|e| fail(&e.to_string()));
+            println!(
+                "Merged: {} added, {} replaced, {} skipped",
+                m.added, m.replaced, m.skipped,
+            );
+            if m.unreadable > 0 {
+                eprintln!(
+                    "warning: {} unreadable entries in {src} \
+                     (see verify)",
+                    m.unreadable,
+                );
+            }
+        }
+        "dedupe" => {
+            let tol = parse_flag(&args, "--tol").unwrap_or(1e-3);
+            let n = catalog::dedupe(&results, tol)
+                .unwrap_or_else(|e| fail(&e.to_string()));
+            println!("Removed {n} near-duplicates");
+        }
+        "import" => run_import(&args, &results),
+        "classify" => run_classify(&args, &results, &opts),
+        "render" => run_render(&args, &results),
+        "histogram" => run_histogram(&args, &results),
+        "list" => run_list(&args, &results),
+        "stats" => run_stats(&args, &results),
+        "verify" => run_verify(&args, &results),
+        _ => {
+            usage();
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Export the PSD of one trajectory coordinate.
+fn run_psd(args: &[String], dir: &Path) {
+    let id = require_id(args);
+    let axis = match flag_str(args, "--coord") {
+        None | Some("x") => 0,
+        Some("y") => 1,
+        Some("z") => 2,
+        Some(c) => fail(&format!("bad --coord {c}")),
+    };
+    let entry = catalog::load(dir, id)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    if entry.resampled {
+        fail("trajectory is arc-length resampled: no time axis");
+    }
+    let series: Vec<f64> = entry
+        .trajectory
+        .iter()
+        .map(|s| s[axis])
+        .collect();
+    let psd = analysis::power_spectrum(
+        &series,
+        search::TRAJ_DT,
+    );
+    let out = flag_str(args, "--out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(format!("{id:016x}.psd.csv"))
+        });
+    if let Err(e) = analysis::export_psd_csv(&psd, &out)
+    {
+        fail(&e.to_string());
+    }
+    println!("Wrote {}", out.display());
+}
+
+/// Export a catalog trajectory as CSV, npy or PLY.
+fn run_export(args: &[String], dir: &Path) {
+    if has_flag(args, "--catalog") {
+        return run_export_meta(args, dir);
+    }
+    let id = require_id(args);
+    let mut entry = catalog::load(dir, id)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    let format = flag_str(args, "--format").unwrap_or("csv");
+    match format {
+        "csv" | "npy" | "ply" => {}
+        "sprott" => {
+            let c = entry
+                .coeff_array()
+                .unwrap_or_else(|| fail("bad coefficients"));
+            match sprott::emit(&c) {
+                Ok(code) => println!("{code}"),
+                Err(e) => fail(&e),
+            }
+            return;
+        }
+        f => fail(&format!("bad --format {f}")),
+    }
+    let n = parse_flag(args, "--points")
+        .unwrap_or(entry.trajectory.len());
+    match flag_str(args, "--sample") {
+        None | Some("time") if entry.resampled => fail(
+            "trajectory is arc-length resampled; \
+             use --sample arclength",
+        ),
+        None | Some("time") => {
+            entry.trajectory = analysis::resample_by_time(
+                &entry.trajectory,
+                n,
+            );
+        }
+        Some("arclength") => {
+            entry.trajectory =
+                analysis::resample_by_arclength(
+                    &entry.trajectory,
+                    n,
+                );
+        }
+        Some(m) => fail(&format!("bad --sample {m}")),
+    }
+    let out = flag_str(args, "--out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(format!("{id:016x}.{format}"))
+        });
+    let res = match format {
+        "npy" => catalog::export_npy(&entry, &out),
+        "ply" if has_flag(args, "--color-speed") => {
+            let c = entry
+                .coeff_array()
+                .unwrap_or_else(|| fail("bad coefficients"));
+            analysis::export_ply_speed(&c, &entry.trajectory, &out)
+        }
+        "ply" => analysis::export_ply(&entry.trajectory, &out),
+        _ => catalog::export_csv(&entry, &out),
+    };
+    if let Err(e) = res {
+        fail(&e.to_string());
+    }
+    println!("Wrote {}", out.display());
+}
+
+/// Export metadata of every catalog entry as `.npy`.
+fn run_export_meta(args: &[String], dir: &Path) {
+    if flag_str(args, "--format") != Some("npy") {
+        fail("--catalog export needs --format npy");
+    }
+    let out = flag_str(args, "--out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("catalog.npy"));
+    let n = catalog::export_meta_npy(dir, &out)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    println!("Wrote {} ({n} entries)", out.display());
+}
+
+/// Import a Sprott code; catalog it if chaotic.
+fn run_import(args: &[String], dir: &Path) {
+    if flag_str(args, "--format") != Some("sprott") {
+        fail("import needs --format sprott");
+    }
+    let code = args
+        .last()
+        .filter(|_| args.len() > 4)
+        .unwrap_or_else(|| fail("missing code"));
+    let c = sprott::parse(code)
+        .unwrap_or_else(|e| fail(&e));
+    match classify::classify(&c) {
+        Dynamics::Chaotic(data) | Dynamics::Hyperchaotic(data) => {
+            let entry = search::make_entry(&c, &data, "import");
+            if let Err(e) = catalog::save(dir, &entry) {
+                fail(&e.to_string());
+            }
+            println!(
+                "chaotic: id={:016x} λ1={:.4} dim={:.3}",
+                entry.id, data.spectrum[0], data.ky_dim,
+            );
+        }
+        other => println!("not chaotic: {other:?}"),
+    }
+}
+
+/// Classify one system from `--coeffs`, a TOML
+/// `--system` file or a catalog entry, printing
+/// the verdict.
+fn run_classify(args: &[String], dir: &Path, opts: &SearchOpts) {
+    let from_id = flag_str(args, "--from-id");
+    let c = match (flag_str(args, "--coeffs"), from_id) {
+        (Some(v), _) => {
+            parse_coeffs(v).unwrap_or_else(|e| fail(&e))
+        }
+        (None, Some(h)) => {
+            let id = u64::from_str_radix(h, 16).unwrap_or_else(|_| {
+                fail(&format!("bad --from-id {h}"))
+            });
+            let dir = flag_str(args, "--dir").map_or(dir, Path::new);
+            catalog::load(dir, id)
+                .unwrap_or_else(|e| fail(&e.to_string()))
+                .coeff_array()
+                .unwrap_or_else(|| fail("entry has bad coefficients"))
+        }
+        (None, None) => match flag_str(args, "--system") {
+            Some(p) => system::load(Path::new(p))
+                .unwrap_or_else(|e| fail(&format!("{p}: {e}"))),
+            None => fail("classify needs --coeffs, --system or --from-id"),
+        },
+    };
+    println!("{}", ode::format_equations(&c));
+    let (kind, d) = match classify::classify_with(&c, &opts.classify) {
+        Dynamics::Chaotic(d) => ("chaotic", d),
+        Dynamics::Hyperchaotic(d) => ("hyperchaotic", d),
+        other => {
+            println!("{other:?}");
+            return;
+        }
+    };
+    let [l1, l2, l3] = d.spectrum;
+    println!(
+        "{kind}: spectrum=[{l1:.4}, {l2:.4}, {l3:.4}] \
+         ky_dim={:.3}",
+        d.ky_dim,
+    );
+}
+
+/// Parse a full comma-separated coefficient vector.
+fn parse_coeffs(v: &str) -> Result<ode::Coeffs, String> {
+    let p: Vec<f64> = v
+        .split(',')
+        .map(|t| {
+            let t = t.trim();
+            t.parse().map_err(|_| format!("bad coefficient {t:?}"))
+        })
+        .collect::<Result<_, _>>()?;
+    let n = p.len();
+    p.try_into().map_err(|_| {
+        format!("expected {} coefficients, got {n}", ode::NCOEFFS)
+    })
+}
+
+/// Report ids unique to / shared by two catalogs.
+fn run_diff(args: &[String]) {
+    if args.len() < 4 {
+        usage();
+        std::process::exit(1);
+    }
+    let d = catalog::diff(
+        Path::new(&args[2]),
+        Path::new(&args[3]),
+    )
+    .unwrap_or_else(|e| fail(&e.to_string()));
+    println!(
+        "only {}: {}  only {}: {}  shared: {}",
+        args[2],
+        d.only_a.len(),
+        args[3],
+        d.only_b.len(),
+        d.shared.len(),
+    );
+    for (tag, ids) in [("<", &d.only_a), (">", &d.only_b)]
+    {
+        for id in ids {
+            println!("{tag} {id:016x}");
+        }
+    }
+}
+
+/// Render a catalog entry to a PPM image.
+fn run_render(args: &[String], dir: &Path) {
+    let id = require_id(args);
+    let entry = catalog::load(dir, id)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    let mut traj = entry.trajectory.clone();
+    // Stretch map: draw the orbit the exponents
+    // were measured along.
+    let stretch = has_flag(args, "--stretch-map").then(|| {
+        let loc = stretch_map(&entry);
+        traj = loc.iter().map(|p| p.0).collect();
+        loc
+    });
+    if has_flag(args, "--fold-symmetry") {
+        let c = entry
+            .coeff_array()
+            .unwrap_or_else(|| fail("bad coefficients"));
+        let syms = ode::symmetries(&c, 1e-12);
+        traj = analysis::fold_symmetry(&traj, &syms);
+    }
+    if !has_flag(args, "--raw-pose") {
+        traj = match &entry.pose {
+            Some(tf) => traj.iter().map(|p| tf.apply(p)).collect(),
+            None => analysis::canonical_pose(&traj).0,
+        };
+    }
+    let size = parse_flag(args, "--size").unwrap_or(512);
+    let view = View::default();
+    let img = if let Some(loc) = stretch {
+        let pts: Vec<_> =
+            traj.iter().zip(loc).map(|(p, (_, l))| (*p, l)).collect();
+        render::render_scalar(&pts, &view, size)
+    } else if has_flag(args, "--perspective") {
+        let mut cam = render::Perspective::auto(&traj);
+        if let Some(v) = flag_str(args, "--camera") {
+            cam.eye = parse_vec3(v)
+                .unwrap_or_else(|| fail("bad --camera x,y,z"));
+        }
+        if let Some(deg) = parse_flag::<f64>(args, "--fov") {
+            cam.fov = deg.to_radians();
+        }
+        render::render_perspective(&traj, &cam, size)
+    } else if has_flag(args, "--anaglyph") {
+        render::render_anaglyph(&traj, &view, size, 0.06)
+    } else {
+        render::render(&traj, &view, size)
+    };
+    let out = flag_str(args, "--out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(format!("{id:016x}.ppm"))
+        });
+    if let Err(e) = img.write_ppm(&out) {
+        fail(&e.to_string());
+    }
+    println!("Wrote {}", out.display());
+}
+
+/// Print catalog entries passing a filter.
+fn run_list(args: &[String], dir: &Path) {
+    let sort = flag_str(args, "--sort").map(|k| {
+        k.parse::<catalog::SortKey>()
+            .unwrap_or_else(|e| fail(&e))
+    });
+    let f = catalog::QueryFilter {
+        min_lambda1: parse_flag(args, "--min-lyap"),
+        max_lambda1: parse_flag(args, "--max-lyap"),
+        min_dim: parse_flag(args, "--min-dim"),
+        max_dim: parse_flag(args, "--max-dim"),
+        method: flag_str(args, "--method").map(String::from),
+        max_nonzero: parse_flag(args, "--max-terms"),
+        sort,
+        limit: parse_flag(args, "--limit"),
+    };
+    let hits = catalog::query(dir, &f)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    for e in &hits {
+        println!(
+            "{:016x} λ1={:.4} dim={:.3} {}",
+            e.id, e.spectrum[0], e.ky_dim, e.method,
+        );
+    }
+    println!("{} entries", hits.len());
+}
+
+/// Catalog overview: count, λ1 spread, KY
+/// dimension buckets and discovery methods.
+fn run_stats(args: &[String], dir: &Path) {
+    let dir = flag_str(args, "--dir").map_or(dir, Path::new);
+    let s = catalog::stats(dir)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    println!("{} entries in {}", s.count, dir.display());
+    let Some([lo, med, hi]) = s.lambda1 else {
+        return;
+    };
+    println!("lambda1: min {lo:.4} median {med:.4} max {hi:.4}");
+    println!("ky_dim:");
+    for (a, b, n) in s.ky_dim.bins() {
+        println!("  [{a:.3}, {b:.3}) {n}");
+    }
+    println!("method:");
+    for (m, n) in &s.methods {
+        let m = if m.is_empty() { "(none)" } else { m };
+        println!("  {m:<12} {n}");
+    }
+    if let Some(e) = &s.strongest {
+        println!("strongest: {:016x} λ1={:.4}", e.id, e.spectrum[0]);
+        if let Some(c) = e.coeff_array() {
+            println!("{}", ode::format_equations(&c));
+        }
+    }
+}
+
+/// List entry files or trajectories that fail to
+/// load; exits nonzero if there are any.
+fn run_verify(args: &[String], dir: &Path) {
+    let dir = flag_str(args, "--dir").map_or(dir, Path::new);
+    let rep = catalog::load_report(dir)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    let mut bad = rep.errors;
+    for e in rep.entries.iter().filter(|e| e.trajectory.is_empty()) {
+        if let Err(err) = catalog::load_trajectory(dir, e.id) {
+            let p = catalog::traj_location(dir, e.id);
+            bad.push((p, err.to_string()));
+        }
+    }
+    for (p, e) in &bad {
+        println!("{}: {e}", p.display());
+    }
+    println!("{} entries, {} unreadable", rep.entries.len(), bad.len());
+    if !bad.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Text histograms of λ1 and KY dimension over
+/// the catalog, or CSV with --out.
+fn run_histogram(args: &[String], dir: &Path) {
+    let entries = catalog::load_all(dir)
+        .unwrap_or_else(|e| fail(&e.to_string()));
+    let bins = parse_flag(args, "--bins").unwrap_or(20);
+    let series = |f: fn(&catalog::Entry) -> f64| {
+        let v: Vec<f64> = entries.iter().map(f).collect();
+        analysis::Histogram::new(&v, bins)
+    };
+    let hists = [
+        ("lambda1", series(|e| e.spectrum[0])),
+        ("ky_dim", series(|e| e.ky_dim)),
+    ];
+    if let Some(out) = flag_str(args, "--out") {
+        let mut csv = String::from("metric,lo,hi,count\n");
+        for (name, h) in &hists {
+            for (a, b, n) in h.bins() {
+                csv += &format!("{name},{a},{b},{n}\n");
+            }
+        }
+        if let Err(e) = std::fs::write(out, csv) {
+            fail(&e.to_string());
+        }
+        println!("Wrote {out}");
+        return;
+    }
+    println!("{} entries", entries.len());
+    for (name, h) in &hists {
+        println!("{name}:");
+        let max = h.counts.iter().copied().max().unwrap_or(0);
+        for (a, b, n) in h.bins() {
+            let bar = "#".repeat((40 * n).div_ceil(max.max(1)));
+            let line = format!("  [{a:8.4}, {b:8.4}) {n:6} {bar}");
+            println!("{}", line.trim_end());
+        }
+    }
+}
+
+/// Closing line of a search run.
+fn print_summary(s: &SearchSummary) {
+    let n = s.evaluated;
+    print!(
+        "Done. {n} evaluated, {} chaotic ({:.2}%) in {:.1}s",
+        s.found,
+        100.0 * s.found as f64 / n.max(1) as f64,
+        s.elapsed.as_secs_f64(),
+    );
+    match s.best_fitness.last() {
+        Some(f) => println!(", best fitness {f:.4}"),
+        None => println!(),
+    }
+    println!(
+        "  {:.1} evals/s; classify {:.2}s, catalog I/O {:.2}s",
+        s.rate(),
+        s.classify_time.as_secs_f64(),
+        s.io_time.as_secs_f64(),
+    );
+}
+
+/// Local finite-time λ1 along the entry's orbit,
+/// one point per WINDOW-step renorm.
+fn stretch_map(entry: &catalog::Entry) -> Vec<([f64; 3], f64)> {
+    // Let the tangent vector align before measuring,
+    // and average over a window past single steps.
+    const TRANSIENT: usize = 1000;
+    const WINDOW: usize = 10;
+    let c = entry
+        .coeff_array()
+        .unwrap_or_else(|| fail("bad coefficients"));
+    let s0 = entry.trajectory.first().copied().unwrap_or([0.1; 3]);
+    let n = entry.trajectory.len();
+    let loc = lyapunov::local_exponents(
+        &c,
+        &s0,
+        &[1.0, 1.0, 1.0],
+        search::TRAJ_DT,
+        TRANSIENT,
+        n,
+        WINDOW,
+    );
+    if loc.is_empty() {
+        fail("orbit diverged");
+    }
+    loc
+}
+
+/// Search options: command-line flags over `base`.
+fn search_opts(args: &[String], base: SearchOpts) -> SearchOpts {
+    let flag = |f| has_flag(args, f);
+    let c = base.classify;
+    let (f, b) = (base.fitness, base.band);
+    SearchOpts {
+        canonical: base.canonical || flag("--canonical"),
+        classify: ClassifyConfig {
+            calibrate: c.calibrate || flag("--calibrate"),
+            early_stop: if flag("--early-stop") {
+                Some(c.early_stop.unwrap_or_default())
+            } else {
+                c.early_stop
+            },
+            starts: parse_flag(args, "--classify-starts")
+                .unwrap_or(c.starts),
+            div_thresh: parse_flag(args, "--div-thresh")
+                .unwrap_or(c.div_thresh),
+            fp_var: parse_flag(args, "--fp-var").unwrap_or(c.fp_var),
+            chaos_thresh: parse_flag(args, "--chaos-thresh")
+                .unwrap_or(c.chaos_thresh),
+            dt: parse_flag(args, "--classify-dt").unwrap_or(c.dt),
+            adaptive_dt: c.adaptive_dt || flag("--adaptive-dt"),
+            transient: parse_flag(args, "--transient")
+                .unwrap_or(c.transient),
+            classify_steps: parse_flag(args, "--classify-steps")
+                .unwrap_or(c.classify_steps),
+            lyap_steps: parse_flag(args, "--lyap-steps")
+                .unwrap_or(c.lyap_steps),
+            renorm: parse_flag(args, "--renorm").unwrap_or(c.renorm),
+            renorm_ceiling: parse_flag(args, "--renorm-ceiling")
+                .or(c.renorm_ceiling),
+        },
+        fitness: FitnessOpts {
+            starts: parse_flag(args, "--fitness-starts")
+                .unwrap_or(f.starts),
+            agg: flag_str(args, "--fitness-agg").map_or(f.agg, |v| {
+                v.parse().unwrap_or_else(|e: String| fail(&e))
+            }),
+            sparsity_penalty: parse_flag(
+                args,
+                "--sparsity-penalty",
+            )
+            .unwrap_or(f.sparsity_penalty),
+            novelty_weight: parse_flag(args, "--novelty-weight")
+                .unwrap_or(f.novelty_weight),
+            novelty_k: parse_flag(args, "--novelty-k")
+                .unwrap_or(f.novelty_k),
+        },
+        mutate: mutate_schedule(args, base.mutate),
+        band: Band {
+            min_dim: parse_flag(args, "--min-dim").or(b.min_dim),
+            max_dim: parse_flag(args, "--max-dim").or(b.max_dim),
+            min_lambda1: parse_flag(args, "--min-lambda1")
+                .or(b.min_lambda1),
+            max_lambda1: parse_flag(args, "--max-lambda1")
+                .or(b.max_lambda1),
+            unstable_dim: parse_flag(args, "--unstable-dim")
+                .or(b.unstable_dim),
+        },
+        chunk_size: parse_flag(args, "--chunk-size")
+            .unwrap_or(base.chunk_size),
+        write_interval_ms: parse_flag(args, "--write-interval-ms")
+            .unwrap_or(base.write_interval_ms),
+        crossover_rate: parse_flag(args, "--crossover-rate")
+            .unwrap_or(base.crossover_rate),
+        tournament_size: parse_flag(args, "--tournament-size")
+            .unwrap_or(base.tournament_size),
+        seed: parse_flag(args, "--seed").or(base.seed),
+        checkpoint_every: parse_flag(args, "--checkpoint-every")
+            .unwrap_or(base.checkpoint_every),
+        resume: base.resume || flag("--resume"),
+        sparsity: parse_flag(args, "--sparsity").or(base.sparsity),
+        seed_from_catalog: base.seed_from_catalog
+            || flag("--seed-from-catalog"),
+        compress: base.compress || flag("--compress"),
+        store_points: parse_flag(args, "--store-points")
+            .or(base.store_points),
+        mask: if flag("--fix-origin") {
+            CoeffMask::origin_equilibrium()
+        } else {
+            base.mask
+        },
+        verbosity: if flag("-q") {
+            Verbosity::Quiet
+        } else if flag("-vv") {
+            Verbosity::Debug
+        } else if flag("-v") {
+            Verbosity::Verbose
+        } else {
+            base.verbosity
+        },
+    }
+}
+
+/// Mutation std schedule over `base`; an explicit
+/// start without end sets both.
+fn mutate_schedule(
+    args: &[String],
+    base: MutateSchedule,
+) -> MutateSchedule {
+    let start: Option<f64> = parse_flag(args, "--mutate-std-start");
+    let end = parse_flag(args, "--mutate-std-end")
+        .or(start)
+        .unwrap_or(base.end);
+    MutateSchedule { start: start.unwrap_or(base.start), end }
+}
+
+/// Parse the mandatory hex `--id` flag.
+fn require_id(args: &[String]) -> u64 {
+    flag_str(args, "--id")
+        .and_then(|v| u64::from_str_radix(v, 16).ok())
+        .unwrap_or_else(|| fail("missing or bad --id"))
+}
+
+/// Parse "x,y,z".
+fn parse_vec3(v: &str) -> Option<[f64; 3]> {
+    let p: Vec<f64> = v
+        .split(',')
+        .map(|t| t.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    p.try_into().ok()
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {msg}");
+    std::process::exit(1);
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn flag_str<'a>(
+    args: &'a [String],
+    flag: &str,
+) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.as_str())
+}
+
+fn parse_flag<T: FromStr>(
+    args: &[String],
+    flag: &str,
+) -> Option<T> {
+    flag_str(args, flag).and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_overrides_config() {
+        let mut base = RunConfig::default().search;
+        base.fitness.starts = 4;
+        base.band.min_dim = Some(2.1);
+        base.mutate.start = 0.5;
+        base.verbosity = Verbosity::Verbose;
+        let args: Vec<String> =
+            ["x", "evolve", "--fitness-starts", "2", "-q"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+        let o = search_opts(&args, base);
+        assert_eq!(o.fitness.starts, 2);
+        assert_eq!(o.verbosity, Verbosity::Quiet);
+        // Untouched by flags: file values stay.
+        assert_eq!(o.band.min_dim, Some(2.1));
+        assert_eq!(o.mutate.start, 0.5);
+        assert_eq!(o.chunk_size, 1);
+    }
+
+    #[test]
+    fn coeffs_need_full_vector() {
+        let v = ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0)
+            .map(|x| x.to_string())
+            .join(",");
+        assert_eq!(
+            parse_coeffs(&v),
+            Ok(ode::lorenz_coeffs(10.0, 28.0, 8.0 / 3.0)),
+        );
+        assert_eq!(
+            parse_coeffs("1,2,3"),
+            Err("expected 30 coefficients, got 3".into()),
+        );
+        assert!(parse_coeffs("1,x").unwrap_err().contains("\"x\""));
+    }
+}
@@ -0,0 +1,83 @@
+// Novelty search: reward behaviour unlike what
+// has already been found.
+
+use crate::lyapunov::LyapData;
+use crate::ode::{self, Coeffs};
+use serde::{Deserialize, Serialize};
+
+/// Behaviour descriptor: [λ1, KY dim, ln bbox vol].
+pub type Descriptor = [f64; 3];
+
+/// Steps of the bounding-box probe orbit.
+const PROBE_STEPS: usize = 5000;
+const PROBE_DT: f64 = 0.01;
+
+/// Descriptor of a chaotic system; the box is
+/// measured over a probe orbit from [0.1; 3].
+pub fn descriptor(c: &Coeffs, data: &LyapData) -> Descriptor {
+    let traj = ode::integrate_traj(c, &[0.1; 3], PROBE_DT, PROBE_STEPS);
+    let mut lo = [f64::MAX; 3];
+    let mut hi = [f64::MIN; 3];
+    for p in &traj[traj.len() / 2..] {
+        for a in 0..3 {
+            lo[a] = lo[a].min(p[a]);
+            hi[a] = hi[a].max(p[a]);
+        }
+    }
+    let vol: f64 = (0..3).map(|a| (hi[a] - lo[a]).max(1e-12)).product();
+    [data.spectrum[0], data.ky_dim, vol.ln()]
+}
+
+/// Descriptors of previously found attractors.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoveltyArchive {
+    pub items: Vec<Descriptor>,
+}
+
+impl NoveltyArchive {
+    pub fn push(&mut self, d: Descriptor) {
+        self.items.push(d);
+    }
+
+    /// Mean distance from d to its k nearest
+    /// archived descriptors (fewer if the archive is
+    /// small; 0 when empty).
+    pub fn novelty(&self, d: &Descriptor, k: usize) -> f64 {
+        let mut dist: Vec<f64> = self
+            .items
+            .iter()
+            .map(|a| {
+                a.iter()
+                    .zip(d)
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect();
+        dist.sort_by(f64::total_cmp);
+        let n = k.max(1).min(dist.len());
+        if n == 0 {
+            return 0.0;
+        }
+        dist[..n].iter().sum::<f64>() / n as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn novelty_knn_mean() {
+        let mut a = NoveltyArchive::default();
+        assert_eq!(a.novelty(&[0.0; 3], 3), 0.0);
+        for x in [1.0, 2.0, 10.0] {
+            a.push([x, 0.0, 0.0]);
+        }
+        let n = a.novelty(&[0.0; 3], 2);
+        assert!((n - 1.5).abs() < 1e-12);
+        // Far from everything scores higher.
+        assert!(a.novelty(&[0.0, 50.0, 0.0], 2) > n);
+        assert!((a.novelty(&[0.0; 3], 10) - 13.0 / 3.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,187 @@
+// Search event hooks: library users observe a run
+// without scraping stdout.
+
+use crate::catalog::Entry;
+use crate::classify::Dynamics;
+use crate::lyapunov::LyapData;
+use crate::search::Verbosity;
+use std::time::Duration;
+
+/// End-of-generation summary for evolve searches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenStats {
+    /// Generations completed (1-based).
+    pub gen: usize,
+    pub best_fit: f64,
+    pub found: usize,
+    pub evaluated: usize,
+    /// Fitness cache hits so far.
+    pub cached: usize,
+    /// Wall time since the search started.
+    pub elapsed: Duration,
+}
+
+/// Receives search events on the driving thread,
+/// in a deterministic order. All methods default
+/// to no-ops; `()` ignores everything.
+pub trait SearchObserver {
+    /// Status line: start, seed, summary.
+    fn on_message(&mut self, _level: Verbosity, _msg: &str) {}
+    /// A candidate classified; evolve passes its
+    /// fitness.
+    fn on_candidate(&mut self, _i: usize, _fit: Option<f64>, _d: &Dynamics) {}
+    /// Random search progress after each batch,
+    /// `elapsed` since the search started.
+    fn on_progress(
+        &mut self,
+        _done: usize,
+        _total: usize,
+        _found: usize,
+        _elapsed: Duration,
+    ) {
+    }
+    /// The n-th chaotic find; `ok` is false if it
+    /// fell outside the band or failed to save.
+    fn on_chaotic(&mut self, _n: usize, _id: u64, _d: &LyapData, _ok: bool) {}
+    /// An entry written to the catalog (with a
+    /// write interval, at the batch's flush).
+    fn on_chaotic_found(&mut self, _e: &Entry) {}
+    fn on_generation(&mut self, _s: &GenStats) {}
+}
+
+impl SearchObserver for () {}
+
+/// The CLI's printed output, gated by verbosity.
+#[derive(Debug, Clone, Copy)]
+pub struct StdoutObserver {
+    pub verbosity: Verbosity,
+}
+
+impl StdoutObserver {
+    pub fn new(verbosity: Verbosity) -> Self {
+        StdoutObserver { verbosity }
+    }
+
+    fn log(&self, level: Verbosity, msg: std::fmt::Arguments) {
+        if self.verbosity.shows(level) {
+            println!("{msg}");
+        }
+    }
+}
+
+/// Evaluations per second.
+fn rate(n: usize, t: Duration) -> f64 {
+    n as f64 / t.as_secs_f64().max(1e-9)
+}
+
+impl SearchObserver for StdoutObserver {
+    fn on_message(&mut self, level: Verbosity, msg: &str) {
+        self.log(level, format_args!("{msg}"));
+    }
+
+    fn on_candidate(&mut self, i: usize, fit: Option<f64>, d: &Dynamics) {
+        match fit {
+            Some(f) => self.log(
+                Verbosity::Debug,
+                format_args!("  fit={f:.4} {d:?}"),
+            ),
+            None => {
+                self.log(Verbosity::Debug, format_args!("  cand {i}: {d:?}"))
+            }
+        }
+    }
+
+    fn on_progress(
+        &mut self,
+        done: usize,
+        total: usize,
+        found: usize,
+        elapsed: Duration,
+    ) {
+        self.log(
+            Verbosity::Normal,
+            format_args!(
+                "  [{done}/{total}] chaotic: {found} ({:.2}%) \
+                 {:.1}/s",
+                100.0 * found as f64 / done.max(1) as f64,
+                rate(done, elapsed),
+            ),
+        );
+    }
+
+    fn on_chaotic(&mut self, n: usize, id: u64, d: &LyapData, saved: bool) {
+        self.log(
+            Verbosity::Normal,
+            format_args!(
+                "  CHAOTIC #{n}: hash={id:016x} \
+                 λ1={:.4} dim={:.3}",
+                d.spectrum[0], d.ky_dim,
+            ),
+        );
+        if !saved {
+            self.log(Verbosity::Verbose, format_args!("    not saved"));
+        }
+    }
+
+    fn on_generation(&mut self, s: &GenStats) {
+        let level = if s.gen.is_multiple_of(50) {
+            Verbosity::Normal
+        } else {
+            Verbosity::Verbose
+        };
+        self.log(
+            level,
+            format_args!(
+                "  gen {}: best_fit={:.4} \
+                 chaotic={} eval={} cached={} {:.1}/s",
+                s.gen,
+                s.best_fit,
+                s.found,
+                s.evaluated,
+                s.cached,
+                rate(s.evaluated, s.elapsed),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Count(usize, usize);
+
+    impl SearchObserver for Count {
+        fn on_candidate(&mut self, _: usize, _: Option<f64>, _: &Dynamics) {
+            self.0 += 1;
+        }
+        fn on_progress(
+            &mut self,
+            done: usize,
+            _: usize,
+            _: usize,
+            _: Duration,
+        ) {
+            self.1 = done;
+        }
+    }
+
+    #[test]
+    fn random_search_reports_every_candidate() {
+        let dir = std::path::Path::new("/tmp/attractor_observer");
+        let _ = std::fs::remove_dir_all(dir);
+        let opts = crate::search::SearchOpts {
+            seed: Some(3),
+            ..Default::default()
+        };
+        let mut obs = Count::default();
+        let sum = crate::search::random_search(6, dir, &opts, &mut obs);
+        assert_eq!((obs.0, obs.1), (6, 6));
+        assert_eq!(sum.evaluated, 6);
+        assert!(sum.best_fitness.is_empty());
+        assert!(sum.classify_time + sum.io_time <= sum.elapsed);
+        assert!(sum.rate() > 0.0);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
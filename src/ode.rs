@@ -1,232 +1,981 @@
-// 3D quadratic ODE system + RK4 integrator.
-// 10 terms per equation: 1,x,y,z,x²,y²,z²,xy,xz,yz
-// 30 coefficients total. Zero allocations in hot path.
-
-pub const NTERMS: usize = 10;
-pub const NCOEFFS: usize = 30;
-
-/// Coefficients for 3 equations, 10 terms each.
-/// Layout: [eq0_term0..eq0_term9,
-///          eq1_term0..eq1_term9,
-///          eq2_term0..eq2_term9]
-pub type Coeffs = [f64; NCOEFFS];
-pub type State = [f64; 3];
-
-/// Evaluate the 10 basis monomials at (x,y,z).
-#[inline(always)]
-pub fn basis(s: &State) -> [f64; NTERMS] {
-    let (x, y, z) = (s[0], s[1], s[2]);
-    [
-        1.0,
-        x,
-        y,
-        z,
-        x * x,
-        y * y,
-        z * z,
-        x * y,
-        x * z,
-        y * z,
-    ]
-}
-
-/// Evaluate one equation: dot product of coeffs[off..off+10]
-/// with the basis monomials.
-#[inline(always)]
-fn eval_eq(
-    c: &Coeffs,
-    off: usize,
-    b: &[f64; NTERMS],
-) -> f64 {
-    let mut v = 0.0;
-    let mut i = 0;
-    while i < NTERMS {
-        v += c[off + i] * b[i];
-        i += 1;
-    }
-    v
-}
-
-/// Evaluate full RHS: ds/dt = f(s).
-#[inline(always)]
-pub fn rhs(c: &Coeffs, s: &State) -> State {
-    let b = basis(s);
-    [
-        eval_eq(c, 0, &b),
-        eval_eq(c, NTERMS, &b),
-        eval_eq(c, 2 * NTERMS, &b),
-    ]
-}
-
-/// Compute Jacobian df_i/dx_j at state s.
-/// Returns 3x3 matrix as [[f64;3];3].
-/// Row i = partials of equation i w.r.t. x,y,z.
-#[inline(always)]
-pub fn jacobian(
-    c: &Coeffs,
-    s: &State,
-) -> [[f64; 3]; 3] {
-    let (x, y, z) = (s[0], s[1], s[2]);
-    let mut j = [[0.0f64; 3]; 3];
-    for eq in 0..3 {
-        let o = eq * NTERMS;
-        // d/dx: terms x(1), x²(2x), xy(y), xz(z)
-        j[eq][0] = c[o + 1]
-            + 2.0 * c[o + 4] * x
-            + c[o + 7] * y
-            + c[o + 8] * z;
-        // d/dy: terms y(1), y²(2y), xy(x), yz(z)
-        j[eq][1] = c[o + 2]
-            + 2.0 * c[o + 5] * y
-            + c[o + 7] * x
-            + c[o + 9] * z;
-        // d/dz: terms z(1), z²(2z), xz(x), yz(y)
-        j[eq][2] = c[o + 3]
-            + 2.0 * c[o + 6] * z
-            + c[o + 8] * x
-            + c[o + 9] * y;
-    }
-    j
-}
-
-/// Single RK4 step. No allocation.
-#[inline(always)]
-pub fn rk4_step(
-    c: &Coeffs,
-    s: &State,
-    dt: f64,
-) -> State {
-    let k1 = rhs(c, s);
-    let s2 = [
-        s[0] + 0.5 * dt * k1[0],
-        s[1] + 0.5 * dt * k1[1],
-        s[2] + 0.5 * dt * k1[2],
-    ];
-    let k2 = rhs(c, &s2);
-    let s3 = [
-        s[0] + 0.5 * dt * k2[0],
-        s[1] + 0.5 * dt * k2[1],
-        s[2] + 0.5 * dt * k2[2],
-    ];
-    let k3 = rhs(c, &s3);
-    let s4 = [
-        s[0] + dt * k3[0],
-        s[1] + dt * k3[1],
-        s[2] + dt * k3[2],
-    ];
-    let k4 = rhs(c, &s4);
-    [
-        s[0] + dt / 6.0
-            * (k1[0] + 2.0*k2[0] + 2.0*k3[0] + k4[0]),
-        s[1] + dt / 6.0
-            * (k1[1] + 2.0*k2[1] + 2.0*k3[1] + k4[1]),
-        s[2] + dt / 6.0
-            * (k1[2] + 2.0*k2[2] + 2.0*k3[2] + k4[2]),
-    ]
-}
-
-/// Integrate for n steps, return final state.
-pub fn integrate(
-    c: &Coeffs,
-    s0: &State,
-    dt: f64,
-    n: usize,
-) -> State {
-    let mut s = *s0;
-    for _ in 0..n {
-        s = rk4_step(c, &s, dt);
-    }
-    s
-}
-
-/// Integrate and collect trajectory.
-pub fn integrate_traj(
-    c: &Coeffs,
-    s0: &State,
-    dt: f64,
-    n: usize,
-) -> Vec<State> {
-    let mut traj = Vec::with_capacity(n + 1);
-    let mut s = *s0;
-    traj.push(s);
-    for _ in 0..n {
-        s = rk4_step(c, &s, dt);
-        traj.push(s);
-    }
-    traj
-}
-
-/// Build Lorenz system coefficients.
-/// dx/dt = sigma*(y - x)
-/// dy/dt = x*(rho - z) - y
-/// dz/dt = x*y - beta*z
-pub fn lorenz_coeffs(
-    sigma: f64,
-    rho: f64,
-    beta: f64,
-) -> Coeffs {
-    let mut c = [0.0; NCOEFFS];
-    // eq0: sigma*y - sigma*x
-    c[1] = -sigma; // x
-    c[2] = sigma;  // y
-    // eq1: rho*x - y - x*z
-    c[NTERMS + 1] = rho;    // x
-    c[NTERMS + 2] = -1.0;   // y
-    c[NTERMS + 8] = -1.0;   // xz
-    // eq2: x*y - beta*z
-    c[2 * NTERMS + 3] = -beta; // z
-    c[2 * NTERMS + 7] = 1.0;   // xy
-    c
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn rk4_harmonic_oscillator() {
-        // dx/dt = y, dy/dt = -x (2D in 3D space)
-        // z unused. Energy = x² + y² conserved.
-        let mut c = [0.0; NCOEFFS];
-        c[2] = 1.0;           // eq0: y
-        c[NTERMS + 1] = -1.0; // eq1: -x
-        let s0: State = [1.0, 0.0, 0.0];
-        let dt = 0.001;
-        let n = 10000; // 10 units of time
-        let sf = integrate(&c, &s0, dt, n);
-        let e0 = s0[0] * s0[0] + s0[1] * s0[1];
-        let ef = sf[0] * sf[0] + sf[1] * sf[1];
-        let err = (ef - e0).abs() / e0;
-        assert!(
-            err < 1e-6,
-            "energy drift {err} too large"
-        );
-    }
-
-    #[test]
-    fn lorenz_bounded() {
-        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
-        let s0: State = [1.0, 1.0, 1.0];
-        let sf = integrate(&c, &s0, 0.005, 10000);
-        let r = (sf[0]*sf[0]
-            + sf[1]*sf[1]
-            + sf[2]*sf[2]).sqrt();
-        assert!(r < 100.0, "Lorenz diverged: r={r}");
-    }
-
-    #[test]
-    fn jacobian_check() {
-        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
-        let s: State = [1.0, 2.0, 3.0];
-        let j = jacobian(&c, &s);
-        // df0/dx = -sigma = -10
-        assert!((j[0][0] - (-10.0)).abs() < 1e-10);
-        // df0/dy = sigma = 10
-        assert!((j[0][1] - 10.0).abs() < 1e-10);
-        // df1/dx = rho - z = 28 - 3 = 25
-        assert!((j[1][0] - 25.0).abs() < 1e-10);
-        // df2/dz = -beta = -8/3
-        let expect = -8.0 / 3.0;
-        assert!((j[2][2] - expect).abs() < 1e-10);
-    }
-}
+// 3D quadratic ODE system + RK4 integrator.
+// 10 terms per equation: 1,x,y,z,x²,y²,z²,xy,xz,yz
+// 30 coefficients total. Zero allocations in hot path.
+
+pub const NTERMS: usize = 10;
+pub const NCOEFFS: usize = 30;
+
+/// Coefficients for 3 equations, 10 terms each.
+/// Layout: [eq0_term0..eq0_term9,
+///          eq1_term0..eq1_term9,
+///          eq2_term0..eq2_term9]
+pub type Coeffs = [f64; NCOEFFS];
+pub type State = [f64; 3];
+
+/// ASCII names of the basis monomials.
+pub const TERM_NAMES: [&str; NTERMS] = [
+    "1", "x", "y", "z", "xx", "yy", "zz", "xy", "xz", "yz",
+];
+
+/// Exponents (x,y,z) of each basis monomial.
+pub const POWERS: [[u8; 3]; NTERMS] = [
+    [0, 0, 0],
+    [1, 0, 0],
+    [0, 1, 0],
+    [0, 0, 1],
+    [2, 0, 0],
+    [0, 2, 0],
+    [0, 0, 2],
+    [1, 1, 0],
+    [1, 0, 1],
+    [0, 1, 1],
+];
+
+/// Coefficients with |c| > tol.
+pub fn nonzero_count(c: &Coeffs, tol: f64) -> usize {
+    c.iter().filter(|v| v.abs() > tol).count()
+}
+
+/// Sign-flip maps s -> (σ0 s0, σ1 s1, σ2 s2), other
+/// than the identity, under which the system is
+/// equivariant: f(σs) = σ f(s). Coefficients with
+/// |c| <= tol count as zero.
+pub fn symmetries(c: &Coeffs, tol: f64) -> Vec<State> {
+    let mut out = Vec::new();
+    for mask in 1..8u8 {
+        let sg = |i: usize| -> f64 {
+            if mask >> i & 1 == 1 { -1.0 } else { 1.0 }
+        };
+        let ok = (0..3).all(|eq| {
+            (0..NTERMS).all(|t| {
+                let p = POWERS[t];
+                let m = sg(0).powi(p[0] as i32)
+                    * sg(1).powi(p[1] as i32)
+                    * sg(2).powi(p[2] as i32);
+                c[eq * NTERMS + t].abs() <= tol
+                    || m == sg(eq)
+            })
+        });
+        if ok {
+            out.push([sg(0), sg(1), sg(2)]);
+        }
+    }
+    out
+}
+
+/// Evaluate the 10 basis monomials at (x,y,z).
+#[inline(always)]
+pub fn basis(s: &State) -> [f64; NTERMS] {
+    let (x, y, z) = (s[0], s[1], s[2]);
+    [
+        1.0,
+        x,
+        y,
+        z,
+        x * x,
+        y * y,
+        z * z,
+        x * y,
+        x * z,
+        y * z,
+    ]
+}
+
+/// Evaluate one equation: dot product of coeffs[off..off+10]
+/// with the basis monomials.
+#[inline(always)]
+fn eval_eq(
+    c: &Coeffs,
+    off: usize,
+    b: &[f64; NTERMS],
+) -> f64 {
+    let mut v = 0.0;
+    let mut i = 0;
+    while i < NTERMS {
+        v += c[off + i] * b[i];
+        i += 1;
+    }
+    v
+}
+
+/// Evaluate full RHS: ds/dt = f(s).
+#[inline(always)]
+pub fn rhs(c: &Coeffs, s: &State) -> State {
+    let b = basis(s);
+    [
+        eval_eq(c, 0, &b),
+        eval_eq(c, NTERMS, &b),
+        eval_eq(c, 2 * NTERMS, &b),
+    ]
+}
+
+/// Compute Jacobian df_i/dx_j at state s.
+/// Returns 3x3 matrix as [[f64;3];3].
+/// Row i = partials of equation i w.r.t. x,y,z.
+#[inline(always)]
+pub fn jacobian(
+    c: &Coeffs,
+    s: &State,
+) -> [[f64; 3]; 3] {
+    let (x, y, z) = (s[0], s[1], s[2]);
+    let mut j = [[0.0f64; 3]; 3];
+    for (eq, row) in j.iter_mut().enumerate() {
+        let o = eq * NTERMS;
+        // d/dx: terms x(1), x²(2x), xy(y), xz(z)
+        row[0] = c[o + 1]
+            + 2.0 * c[o + 4] * x
+            + c[o + 7] * y
+            + c[o + 8] * z;
+        // d/dy: terms y(1), y²(2y), xy(x), yz(z)
+        row[1] = c[o + 2]
+            + 2.0 * c[o + 5] * y
+            + c[o + 7] * x
+            + c[o + 9] * z;
+        // d/dz: terms z(1), z²(2z), xz(x), yz(y)
+        row[2] = c[o + 3]
+            + 2.0 * c[o + 6] * z
+            + c[o + 8] * x
+            + c[o + 9] * y;
+    }
+    j
+}
+
+/// Trace of the Jacobian (the flow's divergence)
+/// at s. Linear in s, so its orbit mean is its
+/// value at the mean state.
+pub fn divergence(c: &Coeffs, s: &State) -> f64 {
+    let (x, y, z) = (s[0], s[1], s[2]);
+    let (o1, o2) = (NTERMS, 2 * NTERMS);
+    c[1] + 2.0 * c[4] * x + c[7] * y + c[8] * z
+        + c[o1 + 2] + 2.0 * c[o1 + 5] * y + c[o1 + 7] * x
+        + c[o1 + 9] * z
+        + c[o2 + 3] + 2.0 * c[o2 + 6] * z + c[o2 + 8] * x
+        + c[o2 + 9] * y
+}
+
+/// Jacobian by central differences of rhs with
+/// step h; a check on the analytic one (exact up
+/// to rounding, since rhs is quadratic).
+pub fn jacobian_fd(c: &Coeffs, s: &State, h: f64) -> [[f64; 3]; 3] {
+    let mut j = [[0.0f64; 3]; 3];
+    for col in 0..3 {
+        let (mut lo, mut hi) = (*s, *s);
+        lo[col] -= h;
+        hi[col] += h;
+        let (fl, fh) = (rhs(c, &lo), rhs(c, &hi));
+        for (row, (a, b)) in j.iter_mut().zip(fl.iter().zip(fh)) {
+            row[col] = (b - a) / (2.0 * h);
+        }
+    }
+    j
+}
+
+/// A complex number, as returned by eig3.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+/// Eigenvalues of a 3x3 matrix: roots of its
+/// characteristic cubic by Cardano, trigonometric
+/// form when all three are real. Sorted by real
+/// part, largest first; a complex pair comes
+/// positive imaginary part first.
+pub fn eig3(m: &[[f64; 3]; 3]) -> [Complex; 3] {
+    // λ³ + a λ² + b λ + c.
+    let a = -(m[0][0] + m[1][1] + m[2][2]);
+    let b = m[0][0] * m[1][1] - m[0][1] * m[1][0]
+        + m[0][0] * m[2][2] - m[0][2] * m[2][0]
+        + m[1][1] * m[2][2] - m[1][2] * m[2][1];
+    let c = -det3(m);
+    // λ = t - a/3 gives t³ + p t + q.
+    let shift = -a / 3.0;
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let disc = q * q / 4.0 + p * p * p / 27.0;
+    let real = |re: f64| Complex { re, im: 0.0 };
+    let mut ev = if disc > 0.0 {
+        let sq = disc.sqrt();
+        let (u, v) = ((-q / 2.0 + sq).cbrt(), (-q / 2.0 - sq).cbrt());
+        let re = shift - (u + v) / 2.0;
+        let im = 3f64.sqrt() / 2.0 * (u - v).abs();
+        [
+            real(shift + u + v),
+            Complex { re, im },
+            Complex { re, im: -im },
+        ]
+    } else if p < 0.0 {
+        let r = 2.0 * (-p / 3.0).sqrt();
+        let cos = (3.0 * q / (p * r)).clamp(-1.0, 1.0);
+        let phi = cos.acos() / 3.0;
+        let tau = std::f64::consts::TAU;
+        [0.0, 1.0, 2.0]
+            .map(|k| real(shift + r * (phi - tau * k / 3.0).cos()))
+    } else {
+        [real(shift); 3]
+    };
+    ev.sort_by(|x, y| {
+        y.re.total_cmp(&x.re).then(y.im.total_cmp(&x.im))
+    });
+    ev
+}
+
+/// Determinant of a 3x3 matrix (rows).
+#[inline]
+pub(crate) fn det3(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// Single RK4 step. No allocation.
+#[inline(always)]
+pub fn rk4_step(
+    c: &Coeffs,
+    s: &State,
+    dt: f64,
+) -> State {
+    let k1 = rhs(c, s);
+    let s2 = [
+        s[0] + 0.5 * dt * k1[0],
+        s[1] + 0.5 * dt * k1[1],
+        s[2] + 0.5 * dt * k1[2],
+    ];
+    let k2 = rhs(c, &s2);
+    let s3 = [
+        s[0] + 0.5 * dt * k2[0],
+        s[1] + 0.5 * dt * k2[1],
+        s[2] + 0.5 * dt * k2[2],
+    ];
+    let k3 = rhs(c, &s3);
+    let s4 = [
+        s[0] + dt * k3[0],
+        s[1] + dt * k3[1],
+        s[2] + dt * k3[2],
+    ];
+    let k4 = rhs(c, &s4);
+    [
+        s[0] + dt / 6.0
+            * (k1[0] + 2.0*k2[0] + 2.0*k3[0] + k4[0]),
+        s[1] + dt / 6.0
+            * (k1[1] + 2.0*k2[1] + 2.0*k3[1] + k4[1]),
+        s[2] + dt / 6.0
+            * (k1[2] + 2.0*k2[2] + 2.0*k3[2] + k4[2]),
+    ]
+}
+
+/// Fixed-point iterations per midpoint step.
+const MIDPOINT_ITERS: usize = 50;
+/// Midpoint iteration stops below this change.
+const MIDPOINT_TOL: f64 = 1e-14;
+
+/// Implicit midpoint step: s1 = s + dt f((s + s1)/2).
+/// Symplectic and exact on quadratic invariants, so
+/// conservative systems don't leak energy as under
+/// RK4. A general quadratic system has no
+/// position/momentum split, hence midpoint rather
+/// than Verlet. Solved by fixed-point iteration,
+/// which converges while dt * |J| < 2.
+pub fn midpoint_step(c: &Coeffs, s: &State, dt: f64) -> State {
+    let f = rhs(c, s);
+    let mut next = [0, 1, 2].map(|i| s[i] + dt * f[i]);
+    for _ in 0..MIDPOINT_ITERS {
+        let mid = [0, 1, 2].map(|i| 0.5 * (s[i] + next[i]));
+        let f = rhs(c, &mid);
+        let mut change = 0.0f64;
+        for i in 0..3 {
+            let v = s[i] + dt * f[i];
+            change = change.max((v - next[i]).abs());
+            next[i] = v;
+        }
+        let size = next.iter().fold(1.0f64, |m, v| m.max(v.abs()));
+        if change <= MIDPOINT_TOL * size {
+            break;
+        }
+    }
+    next
+}
+
+/// Systems advanced together by the batch path.
+pub const LANES: usize = 4;
+/// One value per lane.
+pub type Lanes = [f64; LANES];
+/// Lane-major states: s[k][l] is coord k of system l.
+pub type State4 = [Lanes; 3];
+/// Lane-major coefficients.
+pub type Coeffs4 = [Lanes; NCOEFFS];
+
+/// Interleave four systems for the batch path.
+pub fn pack(cs: &[Coeffs; LANES]) -> Coeffs4 {
+    let mut o = [[0.0; LANES]; NCOEFFS];
+    for (l, c) in cs.iter().enumerate() {
+        for (o, v) in o.iter_mut().zip(c) {
+            o[l] = *v;
+        }
+    }
+    o
+}
+
+/// Interleave four states.
+pub fn pack_states(ss: &[State; LANES]) -> State4 {
+    let mut o = [[0.0; LANES]; 3];
+    for (l, s) in ss.iter().enumerate() {
+        for k in 0..3 {
+            o[k][l] = s[k];
+        }
+    }
+    o
+}
+
+/// State of lane l.
+pub fn lane(s: &State4, l: usize) -> State {
+    [s[0][l], s[1][l], s[2][l]]
+}
+
+/// RHS of four systems at once; the inner loops run
+/// across lanes so they vectorize.
+#[inline(always)]
+pub fn rhs_batch(c: &Coeffs4, s: &State4) -> State4 {
+    let [x, y, z] = s;
+    let mut b = [[0.0; LANES]; NTERMS];
+    for l in 0..LANES {
+        let (x, y, z) = (x[l], y[l], z[l]);
+        let v = [1.0, x, y, z, x * x, y * y, z * z, x * y, x * z, y * z];
+        for t in 0..NTERMS {
+            b[t][l] = v[t];
+        }
+    }
+    let mut o = [[0.0; LANES]; 3];
+    for (eq, o) in o.iter_mut().enumerate() {
+        for (t, b) in b.iter().enumerate() {
+            let c = &c[eq * NTERMS + t];
+            for l in 0..LANES {
+                o[l] += c[l] * b[l];
+            }
+        }
+    }
+    o
+}
+
+/// One RK4 step of four systems in lockstep.
+#[inline]
+pub fn rk4_step_batch(c: &Coeffs4, s: &State4, dt: f64) -> State4 {
+    let at = |k: &State4, h: f64| {
+        let mut o = *s;
+        for (o, k) in o.iter_mut().zip(k) {
+            for l in 0..LANES {
+                o[l] += h * k[l];
+            }
+        }
+        o
+    };
+    let k1 = rhs_batch(c, s);
+    let k2 = rhs_batch(c, &at(&k1, 0.5 * dt));
+    let k3 = rhs_batch(c, &at(&k2, 0.5 * dt));
+    let k4 = rhs_batch(c, &at(&k3, dt));
+    let mut o = *s;
+    for i in 0..3 {
+        for l in 0..LANES {
+            o[i][l] += dt / 6.0
+                * (k1[i][l] + 2.0 * k2[i][l] + 2.0 * k3[i][l] + k4[i][l]);
+        }
+    }
+    o
+}
+
+/// s + dt * Σ w_i k_i (no allocation).
+#[inline(always)]
+fn comb(s: &State, dt: f64, ks: &[(f64, &State)]) -> State {
+    let mut o = *s;
+    for (w, k) in ks {
+        for (oi, ki) in o.iter_mut().zip(k.iter()) {
+            *oi += dt * w * ki;
+        }
+    }
+    o
+}
+
+/// Dormand-Prince 5(4) step: the 5th-order state
+/// and the max-norm of its difference from the
+/// embedded 4th-order one (local error estimate).
+#[inline]
+pub fn rk45_step(
+    c: &Coeffs,
+    s: &State,
+    dt: f64,
+) -> (State, f64) {
+    let k1 = rhs(c, s);
+    let k2 = rhs(c, &comb(s, dt, &[(0.2, &k1)]));
+    let k3 = rhs(c, &comb(s, dt, &[
+        (3.0 / 40.0, &k1),
+        (9.0 / 40.0, &k2),
+    ]));
+    let k4 = rhs(c, &comb(s, dt, &[
+        (44.0 / 45.0, &k1),
+        (-56.0 / 15.0, &k2),
+        (32.0 / 9.0, &k3),
+    ]));
+    let k5 = rhs(c, &comb(s, dt, &[
+        (19372.0 / 6561.0, &k1),
+        (-25360.0 / 2187.0, &k2),
+        (64448.0 / 6561.0, &k3),
+        (-212.0 / 729.0, &k4),
+    ]));
+    let k6 = rhs(c, &comb(s, dt, &[
+        (9017.0 / 3168.0, &k1),
+        (-355.0 / 33.0, &k2),
+        (46732.0 / 5247.0, &k3),
+        (49.0 / 176.0, &k4),
+        (-5103.0 / 18656.0, &k5),
+    ]));
+    let next = comb(s, dt, &[
+        (35.0 / 384.0, &k1),
+        (500.0 / 1113.0, &k3),
+        (125.0 / 192.0, &k4),
+        (-2187.0 / 6784.0, &k5),
+        (11.0 / 84.0, &k6),
+    ]);
+    let k7 = rhs(c, &next);
+    // 5th minus 4th order weights.
+    let e = comb(&[0.0; 3], dt, &[
+        (71.0 / 57600.0, &k1),
+        (-71.0 / 16695.0, &k3),
+        (71.0 / 1920.0, &k4),
+        (-17253.0 / 339200.0, &k5),
+        (22.0 / 525.0, &k6),
+        (-1.0 / 40.0, &k7),
+    ]);
+    let err = e.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+    (next, err)
+}
+
+/// Step-size collapse limit for integrate_adaptive.
+const MIN_DT: f64 = 1e-12;
+
+/// Integrate to t_end with RK45, keeping each
+/// step's error under atol + rtol * |s|. None if
+/// the state blows up or dt collapses (stiff).
+pub fn integrate_adaptive(
+    c: &Coeffs,
+    s0: &State,
+    t_end: f64,
+    rtol: f64,
+    atol: f64,
+) -> Option<State> {
+    let (mut s, mut t) = (*s0, 0.0);
+    let mut dt = (0.01f64).min(t_end);
+    while t < t_end {
+        dt = dt.min(t_end - t);
+        let (next, err) = rk45_step(c, &s, dt);
+        let size = s
+            .iter()
+            .chain(&next)
+            .fold(0.0f64, |m, v| m.max(v.abs()));
+        let ratio = err / (atol + rtol * size);
+        if !ratio.is_finite() {
+            return None;
+        }
+        if ratio <= 1.0 {
+            (s, t) = (next, t + dt);
+        }
+        // Standard safety factor and growth limits.
+        dt *= (0.9 * ratio.powf(-0.2)).clamp(0.2, 5.0);
+        if dt < MIN_DT {
+            return None;
+        }
+    }
+    Some(s)
+}
+
+/// Integrate for n steps, return final state.
+pub fn integrate(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    n: usize,
+) -> State {
+    let mut s = *s0;
+    for _ in 0..n {
+        s = rk4_step(c, &s, dt);
+    }
+    s
+}
+
+/// Integrate and collect trajectory.
+pub fn integrate_traj(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    n: usize,
+) -> Vec<State> {
+    let mut traj = Vec::with_capacity(n + 1);
+    let mut s = *s0;
+    traj.push(s);
+    for _ in 0..n {
+        s = rk4_step(c, &s, dt);
+        traj.push(s);
+    }
+    traj
+}
+
+/// Lazy, allocation-free integrate_traj: yields s0
+/// then each RK4 step, without end (use take).
+#[derive(Debug, Clone)]
+pub struct TrajIter<'a> {
+    c: &'a Coeffs,
+    s: State,
+    dt: f64,
+    started: bool,
+}
+
+impl<'a> TrajIter<'a> {
+    pub fn new(c: &'a Coeffs, s0: &State, dt: f64) -> Self {
+        TrajIter { c, s: *s0, dt, started: false }
+    }
+}
+
+impl Iterator for TrajIter<'_> {
+    type Item = State;
+
+    #[inline]
+    fn next(&mut self) -> Option<State> {
+        if self.started {
+            self.s = rk4_step(self.c, &self.s, self.dt);
+        }
+        self.started = true;
+        Some(self.s)
+    }
+}
+
+/// Integrate until `stop` holds, for at most
+/// `max_steps`. Returns the last state and the step
+/// at which `stop` first held (0 if at s0).
+pub fn integrate_until(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    max_steps: usize,
+    mut stop: impl FnMut(&State) -> bool,
+) -> (State, Option<usize>) {
+    let mut s = *s0;
+    if stop(&s) {
+        return (s, Some(0));
+    }
+    for i in 1..=max_steps {
+        s = rk4_step(c, &s, dt);
+        if stop(&s) {
+            return (s, Some(i));
+        }
+    }
+    (s, None)
+}
+
+/// Target region for first-passage queries.
+#[derive(Debug, Clone, Copy)]
+pub enum Region {
+    Sphere { center: State, radius: f64 },
+    Box { lo: State, hi: State },
+}
+
+impl Region {
+    pub fn contains(&self, p: &State) -> bool {
+        match self {
+            Region::Sphere { center, radius } => {
+                let d2: f64 = p
+                    .iter()
+                    .zip(center)
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum();
+                d2 <= radius * radius
+            }
+            Region::Box { lo, hi } => {
+                (0..3).all(|i| lo[i] <= p[i] && p[i] <= hi[i])
+            }
+        }
+    }
+}
+
+/// Time at which the orbit from s0 first enters
+/// `region` (0 if it starts inside), or None if it
+/// stays out for `max_steps`.
+pub fn first_passage_time(
+    c: &Coeffs,
+    s0: &State,
+    dt: f64,
+    region: &Region,
+    max_steps: usize,
+) -> Option<f64> {
+    integrate_until(c, s0, dt, max_steps, |s| region.contains(s))
+        .1
+        .map(|n| n as f64 * dt)
+}
+
+const PROBE_DT: f64 = 0.005;
+const PROBE_STEPS: usize = 2000;
+
+/// Time-scale factor k: c/k has unit mean speed
+/// along its orbit. Scaling all coefficients by a
+/// constant is a pure time rescaling, so every
+/// rescaled copy of c shares the same c/k.
+/// Integrating c/k at dt matches c at dt/k.
+pub fn time_scale(c: &Coeffs) -> f64 {
+    let n = (c.iter().map(|v| v * v).sum::<f64>()
+        / NCOEFFS as f64)
+        .sqrt();
+    if n == 0.0 || !n.is_finite() {
+        return 1.0;
+    }
+    let u = c.map(|v| v / n);
+    let mut s: State = [0.1; 3];
+    let (mut sum, mut cnt) = (0.0, 0usize);
+    for _ in 0..PROBE_STEPS {
+        let f = rhs(&u, &s);
+        let v = (f[0]*f[0] + f[1]*f[1] + f[2]*f[2])
+            .sqrt();
+        if !v.is_finite() || v > 1e6 {
+            break;
+        }
+        sum += v;
+        cnt += 1;
+        s = rk4_step(&u, &s, PROBE_DT);
+    }
+    let v = if cnt > 0 { sum / cnt as f64 } else { 0.0 };
+    if v > 1e-12 { n * v } else { n }
+}
+
+/// suggest_dt's dt range.
+const SUGGEST_MIN_DT: f64 = 1e-5;
+const SUGGEST_MAX_DT: f64 = 0.05;
+/// dt per unit of the fastest local rate; Lorenz
+/// (rate ~40) lands near the classify DT of 0.005.
+const DT_PER_RATE: f64 = 0.2;
+
+/// Step resolving the fastest local timescale seen
+/// along a short probe from s0: DT_PER_RATE over
+/// the largest row-sum norm of the Jacobian (a
+/// bound on its eigenvalue magnitudes). The probe
+/// shrinks its own step as it goes and stops early
+/// if the orbit escapes.
+pub fn suggest_dt(c: &Coeffs, s0: &State) -> f64 {
+    let mut s = *s0;
+    let mut dt = SUGGEST_MAX_DT;
+    for _ in 0..PROBE_STEPS {
+        let rate = jacobian(c, &s)
+            .iter()
+            .map(|row| row.iter().map(|v| v.abs()).sum::<f64>())
+            .fold(0.0f64, f64::max);
+        if !rate.is_finite() {
+            break;
+        }
+        dt = dt.min(DT_PER_RATE / rate.max(1e-12));
+        let next = rk4_step(c, &s, dt);
+        if !next.iter().all(|v| v.is_finite() && v.abs() < 1e3) {
+            break;
+        }
+        s = next;
+    }
+    dt.clamp(SUGGEST_MIN_DT, SUGGEST_MAX_DT)
+}
+
+/// Canonical time units: (c/k, k).
+pub fn canonicalize(c: &Coeffs) -> (Coeffs, f64) {
+    let k = time_scale(c);
+    (c.map(|v| v / k), k)
+}
+
+/// The system as three lines like
+/// `dx/dt = -10.000*x + 10.000*y`, in basis
+/// order, omitting terms that print as zero.
+/// Terms are spelled as in TERM_NAMES, the keys
+/// of a system file.
+pub fn format_equations(c: &Coeffs) -> String {
+    let mut lines = Vec::with_capacity(3);
+    for (e, var) in ["x", "y", "z"].iter().enumerate() {
+        let mut rhs = String::new();
+        for (t, name) in TERM_NAMES.iter().enumerate() {
+            let v = c[e * NTERMS + t];
+            if (v.abs() * 1e3).round() == 0.0 {
+                continue;
+            }
+            rhs += match (rhs.is_empty(), v < 0.0) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+            rhs += &format!("{:.3}", v.abs());
+            if t > 0 {
+                rhs += &format!("*{name}");
+            }
+        }
+        if rhs.is_empty() {
+            rhs.push('0');
+        }
+        lines.push(format!("d{var}/dt = {rhs}"));
+    }
+    lines.join("\n")
+}
+
+/// Build Lorenz system coefficients.
+/// dx/dt = sigma*(y - x)
+/// dy/dt = x*(rho - z) - y
+/// dz/dt = x*y - beta*z
+pub fn lorenz_coeffs(
+    sigma: f64,
+    rho: f64,
+    beta: f64,
+) -> Coeffs {
+    let mut c = [0.0; NCOEFFS];
+    // eq0: sigma*y - sigma*x
+    c[1] = -sigma; // x
+    c[2] = sigma;  // y
+    // eq1: rho*x - y - x*z
+    c[NTERMS + 1] = rho;    // x
+    c[NTERMS + 2] = -1.0;   // y
+    c[NTERMS + 8] = -1.0;   // xz
+    // eq2: x*y - beta*z
+    c[2 * NTERMS + 3] = -beta; // z
+    c[2 * NTERMS + 7] = 1.0;   // xy
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rk4_harmonic_oscillator() {
+        // dx/dt = y, dy/dt = -x (2D in 3D space)
+        // z unused. Energy = x² + y² conserved.
+        let mut c = [0.0; NCOEFFS];
+        c[2] = 1.0;           // eq0: y
+        c[NTERMS + 1] = -1.0; // eq1: -x
+        let s0: State = [1.0, 0.0, 0.0];
+        let dt = 0.001;
+        let n = 10000; // 10 units of time
+        let sf = integrate(&c, &s0, dt, n);
+        let e0 = s0[0] * s0[0] + s0[1] * s0[1];
+        let ef = sf[0] * sf[0] + sf[1] * sf[1];
+        let err = (ef - e0).abs() / e0;
+        assert!(
+            err < 1e-6,
+            "energy drift {err} too large"
+        );
+    }
+
+    #[test]
+    fn suggested_dt_tracks_timescale() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let dt = suggest_dt(&c, &[0.1; 3]);
+        assert!((0.002..0.01).contains(&dt), "{dt}");
+        let fast = c.map(|v| 10.0 * v);
+        let ratio = dt / suggest_dt(&fast, &[0.1; 3]);
+        assert!((ratio - 10.0).abs() < 0.5, "{ratio}");
+    }
+
+    #[test]
+    fn traj_iter_matches_integrate_traj() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = [1.0, 2.0, 3.0];
+        let lazy: Vec<State> =
+            TrajIter::new(&c, &s0, 0.01).take(501).collect();
+        assert_eq!(lazy, integrate_traj(&c, &s0, 0.01, 500));
+    }
+
+    #[test]
+    fn jacobian_matches_fd() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(17);
+        for _ in 0..20 {
+            let c = crate::ops::rand_coeffs(&mut rng);
+            let s = [(); 3].map(|_| rng.gen_range(-3.0..3.0));
+            let fd = jacobian_fd(&c, &s, 1e-4);
+            for (a, b) in jacobian(&c, &s).iter().zip(fd) {
+                for (x, y) in a.iter().zip(b) {
+                    assert!((x - y).abs() < 1e-8, "{x} vs {y}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn divergence_is_jacobian_trace() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(18);
+        for _ in 0..20 {
+            let c = crate::ops::rand_coeffs(&mut rng);
+            let s = [(); 3].map(|_| rng.gen_range(-3.0..3.0));
+            let j = jacobian(&c, &s);
+            let tr = j[0][0] + j[1][1] + j[2][2];
+            assert!((divergence(&c, &s) - tr).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn midpoint_conserves_energy() {
+        let mut c = [0.0; NCOEFFS];
+        c[2] = 1.0;
+        c[NTERMS + 1] = -1.0;
+        let energy = |s: &State| s[0] * s[0] + s[1] * s[1];
+        let (mut a, mut b) = ([1.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let dt = 0.05;
+        for _ in 0..1_000_000 {
+            a = rk4_step(&c, &a, dt);
+            b = midpoint_step(&c, &b, dt);
+        }
+        let (ea, eb) = ((energy(&a) - 1.0).abs(), (energy(&b) - 1.0).abs());
+        assert!(ea > 1e-5, "RK4 drift {ea}");
+        assert!(eb < 1e-3 * ea, "midpoint {eb} vs RK4 {ea}");
+    }
+
+    #[test]
+    fn batch_matches_scalar() {
+        let cs = [
+            lorenz_coeffs(10.0, 28.0, 8.0 / 3.0),
+            lorenz_coeffs(10.0, 35.0, 8.0 / 3.0),
+            lorenz_coeffs(16.0, 45.0, 4.0),
+            lorenz_coeffs(10.0, 0.5, 8.0 / 3.0),
+        ];
+        let ss = [[1.0, 1.0, 1.0], [0.1, -2.0, 5.0], [3.0; 3], [-1.0; 3]];
+        let c4 = pack(&cs);
+        let mut s4 = pack_states(&ss);
+        let mut ss = ss;
+        for _ in 0..500 {
+            s4 = rk4_step_batch(&c4, &s4, 0.005);
+            for (s, c) in ss.iter_mut().zip(&cs) {
+                *s = rk4_step(c, s, 0.005);
+            }
+        }
+        for (l, s) in ss.iter().enumerate() {
+            let b = lane(&s4, l);
+            for k in 0..3 {
+                assert!((b[k] - s[k]).abs() < 1e-9, "lane {l}");
+            }
+        }
+    }
+
+    #[test]
+    fn lorenz_bounded() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0: State = [1.0, 1.0, 1.0];
+        let sf = integrate(&c, &s0, 0.005, 10000);
+        let r = (sf[0]*sf[0]
+            + sf[1]*sf[1]
+            + sf[2]*sf[2]).sqrt();
+        assert!(r < 100.0, "Lorenz diverged: r={r}");
+    }
+
+    #[test]
+    fn jacobian_check() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s: State = [1.0, 2.0, 3.0];
+        let j = jacobian(&c, &s);
+        // df0/dx = -sigma = -10
+        assert!((j[0][0] - (-10.0)).abs() < 1e-10);
+        // df0/dy = sigma = 10
+        assert!((j[0][1] - 10.0).abs() < 1e-10);
+        // df1/dx = rho - z = 28 - 3 = 25
+        assert!((j[1][0] - 25.0).abs() < 1e-10);
+        // df2/dz = -beta = -8/3
+        let expect = -8.0 / 3.0;
+        assert!((j[2][2] - expect).abs() < 1e-10);
+    }
+
+    #[test]
+    fn lorenz_symmetry() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        assert_eq!(symmetries(&c, 0.0), vec![[-1.0, -1.0, 1.0]]);
+    }
+
+    #[test]
+    fn canonical_time_rescale() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let fast = c.map(|v| 2.5 * v);
+        let (ca, ka) = canonicalize(&c);
+        let (cb, kb) = canonicalize(&fast);
+        for i in 0..NCOEFFS {
+            assert!((ca[i] - cb[i]).abs() < 1e-9);
+        }
+        // Same canonical dt => original dt ratio 2.5.
+        assert!((kb / ka - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rk45_matches_rk4_lorenz() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let s0 = [1.0, 1.0, 1.0];
+        let fixed = integrate(&c, &s0, 1e-4, 10000);
+        let ad = integrate_adaptive(&c, &s0, 1.0, 1e-10, 1e-10)
+            .expect("adaptive failed");
+        for (a, b) in ad.iter().zip(&fixed) {
+            assert!((a - b).abs() < 1e-6, "{ad:?} vs {fixed:?}");
+        }
+        // Embedded error scales as dt^5.
+        let e1 = rk45_step(&c, &s0, 0.01).1;
+        let e2 = rk45_step(&c, &s0, 0.005).1;
+        assert!(e1 / e2 > 16.0, "{e1} {e2}");
+    }
+
+    #[test]
+    fn first_passage_drift() {
+        // dx/dt = 2: reaches the ball x in [9, 11] at t = 4.5.
+        let mut c = [0.0; NCOEFFS];
+        c[0] = 2.0;
+        let ball = Region::Sphere {
+            center: [10.0, 0.0, 0.0],
+            radius: 1.0,
+        };
+        let t = first_passage_time(&c, &[0.0; 3], 0.01, &ball, 1000)
+            .expect("never entered");
+        assert!((t - 4.5).abs() <= 0.011, "t={t}");
+        let inside = [10.0, 0.0, 0.0];
+        let t0 = first_passage_time(&c, &inside, 0.01, &ball, 10);
+        assert_eq!(t0, Some(0.0));
+        assert_eq!(first_passage_time(&c, &[0.0; 3], 0.01, &ball, 100), None);
+    }
+
+    #[test]
+    fn eig3_known_matrices() {
+        let close = |e: [Complex; 3], want: [(f64, f64); 3]| {
+            for (z, (re, im)) in e.iter().zip(want) {
+                let err = (z.re - re).abs() + (z.im - im).abs();
+                assert!(err < 1e-9, "{e:?} vs {want:?}");
+            }
+        };
+        let diag = [[1.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 2.0]];
+        close(eig3(&diag), [(3.0, 0.0), (2.0, 0.0), (1.0, 0.0)]);
+        let id = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        close(eig3(&id), [(1.0, 0.0); 3]);
+        // Rotation-scaling block plus a decaying axis.
+        let rot = [[-0.5, -2.0, 0.0], [2.0, -0.5, 0.0], [0.0, 0.0, -3.0]];
+        close(eig3(&rot), [(-0.5, 2.0), (-0.5, -2.0), (-3.0, 0.0)]);
+        // Lorenz at the origin.
+        let j = jacobian(&lorenz_coeffs(10.0, 28.0, 8.0 / 3.0), &[0.0; 3]);
+        let sq = 1201f64.sqrt();
+        close(eig3(&j), [
+            ((sq - 11.0) / 2.0, 0.0),
+            (-8.0 / 3.0, 0.0),
+            ((-sq - 11.0) / 2.0, 0.0),
+        ]);
+    }
+
+    #[test]
+    fn lorenz_equations_print() {
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        assert_eq!(
+            format_equations(&lor),
+            "dx/dt = -10.000*x + 10.000*y\n\
+             dy/dt = 28.000*x - 1.000*y - 1.000*xz\n\
+             dz/dt = -2.667*z + 1.000*xy",
+        );
+        let mut c = [0.0; NCOEFFS];
+        c[0] = 0.5;
+        c[NTERMS + 6] = -2.0;
+        c[2 * NTERMS + 1] = 1e-17;
+        assert_eq!(
+            format_equations(&c),
+            "dx/dt = 0.500\ndy/dt = -2.000*zz\ndz/dt = 0",
+        );
+    }
+}
@@ -0,0 +1,158 @@
+// D-dimensional quadratic ODE systems + RK4.
+// Basis per equation: 1, x_i, x_i² then x_i x_j
+// (i < j), so D = 3 matches ode.rs term order.
+
+use crate::ode::{Coeffs, NTERMS};
+
+/// Basis size for dimension d.
+pub const fn nterms(d: usize) -> usize {
+    1 + d + d * (d + 1) / 2
+}
+
+/// Quadratic system in D variables; coeffs hold
+/// D equations of nterms(D) terms each.
+#[derive(Debug, Clone)]
+pub struct Quad<const D: usize> {
+    pub coeffs: Vec<f64>,
+    /// (i, j) of each quadratic term.
+    pairs: Vec<(usize, usize)>,
+}
+
+impl<const D: usize> Quad<D> {
+    /// Panics unless coeffs.len() == D * nterms(D).
+    pub fn new(coeffs: Vec<f64>) -> Self {
+        assert_eq!(coeffs.len(), D * nterms(D));
+        let sq = (0..D).map(|i| (i, i));
+        let cross = (0..D).flat_map(|i| (i + 1..D).map(move |j| (i, j)));
+        let pairs = sq.chain(cross).collect();
+        Quad { coeffs, pairs }
+    }
+
+    /// Evaluate ds/dt = f(s).
+    #[inline]
+    pub fn rhs(&self, s: &[f64; D]) -> [f64; D] {
+        let nt = nterms(D);
+        let mut out = [0.0; D];
+        for (eq, v) in out.iter_mut().enumerate() {
+            let c = &self.coeffs[eq * nt..(eq + 1) * nt];
+            *v = c[0];
+            for (ci, x) in c[1..=D].iter().zip(s) {
+                *v += ci * x;
+            }
+            for (ci, &(i, j)) in c[D + 1..].iter().zip(&self.pairs) {
+                *v += ci * s[i] * s[j];
+            }
+        }
+        out
+    }
+
+    /// Jacobian df_i/dx_j at s.
+    pub fn jacobian(&self, s: &[f64; D]) -> [[f64; D]; D] {
+        let nt = nterms(D);
+        let mut j = [[0.0; D]; D];
+        for (eq, row) in j.iter_mut().enumerate() {
+            let c = &self.coeffs[eq * nt..(eq + 1) * nt];
+            row.copy_from_slice(&c[1..=D]);
+            for (ci, &(a, b)) in c[D + 1..].iter().zip(&self.pairs) {
+                row[a] += ci * s[b];
+                row[b] += ci * s[a];
+            }
+        }
+        j
+    }
+
+    /// One RK4 step.
+    pub fn rk4_step(&self, s: &[f64; D], dt: f64) -> [f64; D] {
+        let at = |k: &[f64; D], h: f64| {
+            let mut o = *s;
+            for (o, k) in o.iter_mut().zip(k) {
+                *o += h * k;
+            }
+            o
+        };
+        let k1 = self.rhs(s);
+        let k2 = self.rhs(&at(&k1, 0.5 * dt));
+        let k3 = self.rhs(&at(&k2, 0.5 * dt));
+        let k4 = self.rhs(&at(&k3, dt));
+        let mut o = *s;
+        for i in 0..D {
+            o[i] += dt / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+        }
+        o
+    }
+
+    /// Integrate n RK4 steps.
+    pub fn integrate(&self, s0: &[f64; D], dt: f64, n: usize) -> [f64; D] {
+        let mut s = *s0;
+        for _ in 0..n {
+            s = self.rk4_step(&s, dt);
+        }
+        s
+    }
+
+    /// Integrate and collect trajectory.
+    pub fn integrate_traj(
+        &self,
+        s0: &[f64; D],
+        dt: f64,
+        n: usize,
+    ) -> Vec<[f64; D]> {
+        let mut s = *s0;
+        let mut traj = Vec::with_capacity(n + 1);
+        traj.push(s);
+        for _ in 0..n {
+            s = self.rk4_step(&s, dt);
+            traj.push(s);
+        }
+        traj
+    }
+}
+
+/// Lift a 3D system into the generic form.
+pub fn from3(c: &Coeffs) -> Quad<3> {
+    const _: () = assert!(nterms(3) == NTERMS);
+    Quad::new(c.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::{self, lorenz_coeffs};
+
+    #[test]
+    fn dim3_matches_ode() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let q = from3(&c);
+        let s = [1.5, -2.0, 20.0];
+        assert_eq!(q.rhs(&s), ode::rhs(&c, &s));
+        for (a, b) in q.jacobian(&s).iter().zip(ode::jacobian(&c, &s)) {
+            for (x, y) in a.iter().zip(b) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+        let (a, b) = (
+            q.integrate(&s, 0.005, 200),
+            ode::integrate(&c, &s, 0.005, 200),
+        );
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn dim4_oscillators() {
+        // x' = y, y' = -x, z' = w, w' = -4z.
+        let nt = nterms(4);
+        let mut c = vec![0.0; 4 * nt];
+        c[2] = 1.0;
+        c[nt + 1] = -1.0;
+        c[2 * nt + 4] = 1.0;
+        c[3 * nt + 3] = -4.0;
+        let q = Quad::<4>::new(c);
+        let t = 2.0 * std::f64::consts::PI;
+        let s = q.integrate(&[1.0, 0.0, 1.0, 0.0], t / 1000.0, 1000);
+        for (x, y) in s.iter().zip([1.0, 0.0, 1.0, 0.0]) {
+            assert!((x - y).abs() < 1e-6, "{s:?}");
+        }
+    }
+}
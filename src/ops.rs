@@ -0,0 +1,288 @@
+// Evolutionary operators. Each takes its RNG as an
+// argument so runs and tests are reproducible.
+
+use crate::ode::{Coeffs, NTERMS};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Coefficients are drawn and clamped to ±this.
+pub const COEFF_RANGE: f64 = 2.0;
+
+/// Uniform random coefficients in ±COEFF_RANGE.
+pub fn rand_coeffs(rng: &mut impl Rng) -> Coeffs {
+    let mut c = [0.0; crate::ode::NCOEFFS];
+    for v in c.iter_mut() {
+        *v = rng.gen_range(-COEFF_RANGE..COEFF_RANGE);
+    }
+    c
+}
+
+/// Coefficients with k (at most NCOEFFS) nonzero
+/// slots chosen uniformly, each uniform in
+/// ±COEFF_RANGE.
+pub fn rand_coeffs_sparse(rng: &mut impl Rng, k: usize) -> Coeffs {
+    let n = crate::ode::NCOEFFS;
+    let mut c = [0.0; crate::ode::NCOEFFS];
+    for i in rand::seq::index::sample(rng, n, k.min(n)) {
+        c[i] = rng.gen_range(-COEFF_RANGE..COEFF_RANGE);
+    }
+    c
+}
+
+/// Coefficients pinned during generation and
+/// mutation, as (index, value); the search explores
+/// the remaining free slots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoeffMask {
+    pub fixed: Vec<(usize, f64)>,
+}
+
+impl CoeffMask {
+    /// Zero constant terms: the origin is always an
+    /// equilibrium.
+    pub fn origin_equilibrium() -> Self {
+        CoeffMask::default()
+            .freeze(0, 0.0)
+            .freeze(NTERMS, 0.0)
+            .freeze(2 * NTERMS, 0.0)
+    }
+
+    /// Also pin slot i to v.
+    pub fn freeze(mut self, i: usize, v: f64) -> Self {
+        self.fixed.push((i, v));
+        self
+    }
+
+    /// Overwrite the pinned slots of c.
+    pub fn apply(&self, c: &mut Coeffs) {
+        for &(i, v) in &self.fixed {
+            if let Some(x) = c.get_mut(i) {
+                *x = v;
+            }
+        }
+    }
+}
+
+/// Mutate coefficients with Gaussian noise.
+pub fn mutate(
+    c: &mut Coeffs,
+    std: f64,
+    rng: &mut impl Rng,
+) {
+    for v in c.iter_mut() {
+        // Box-Muller for normal dist w/o dep.
+        let u1: f64 = rng.gen_range(1e-10..1.0);
+        let u2: f64 = rng.gen_range(0.0..
+            std::f64::consts::TAU);
+        let z = (-2.0 * u1.ln()).sqrt() * u2.cos();
+        *v += std * z;
+        *v = v.clamp(-COEFF_RANGE, COEFF_RANGE);
+    }
+}
+
+/// Uniform crossover: each gene from a or b.
+pub fn crossover(
+    a: &Coeffs,
+    b: &Coeffs,
+    rng: &mut impl Rng,
+) -> Coeffs {
+    let mut c = *a;
+    for (v, &w) in c.iter_mut().zip(b) {
+        if rng.gen_bool(0.5) {
+            *v = w;
+        }
+    }
+    c
+}
+
+/// Fittest of `k` individuals drawn uniformly.
+pub fn tournament<'a>(
+    pop: &'a [(Coeffs, f64)],
+    k: usize,
+    rng: &mut impl Rng,
+) -> &'a Coeffs {
+    let mut best = &pop[rng.gen_range(0..pop.len())];
+    for _ in 1..k {
+        let c = &pop[rng.gen_range(0..pop.len())];
+        if c.1 > best.1 {
+            best = c;
+        }
+    }
+    &best.0
+}
+
+/// Next generation: the single best survives as
+/// pop[0]; every other slot gets a mutated child of
+/// tournament-picked parents (fitness reset to -inf
+/// for re-evaluation). A `crossover_rate` fraction
+/// of children cross two parents; the rest clone.
+/// Children keep `mask`'s pinned values.
+pub fn select(
+    pop: &mut [(Coeffs, f64)],
+    std: f64,
+    crossover_rate: f64,
+    tournament_size: usize,
+    mask: &CoeffMask,
+    rng: &mut impl Rng,
+) {
+    pop.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let old = pop.to_vec();
+    let k = tournament_size.max(1);
+    let rate = crossover_rate.clamp(0.0, 1.0);
+    for slot in pop.iter_mut().skip(1) {
+        let a = tournament(&old, k, rng);
+        let mut child = if rng.gen_bool(rate) {
+            let b = tournament(&old, k, rng);
+            crossover(a, b, rng)
+        } else {
+            *a
+        };
+        mutate(&mut child, std, rng);
+        mask.apply(&mut child);
+        *slot = (child, f64::NEG_INFINITY);
+    }
+}
+
+/// Ring migration: the top k of island i (by
+/// fitness) replace the worst k of island i + 1.
+/// Migrants keep their fitness.
+pub fn migrate(islands: &mut [Vec<(Coeffs, f64)>], k: usize) {
+    for isl in islands.iter_mut() {
+        isl.sort_by(|a, b| b.1.total_cmp(&a.1));
+    }
+    let m = islands.len();
+    let best: Vec<Vec<(Coeffs, f64)>> = islands
+        .iter()
+        .map(|isl| isl.iter().take(k).copied().collect())
+        .collect();
+    for (i, mig) in best.into_iter().enumerate() {
+        let dst = &mut islands[(i + 1) % m];
+        let n = dst.len();
+        let k = mig.len().min(n);
+        dst[n - k..].copy_from_slice(&mig[..k]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SEEDS: std::ops::Range<u64> = 0..50;
+
+    fn rng(seed: u64) -> StdRng {
+        StdRng::seed_from_u64(seed)
+    }
+
+    #[test]
+    fn migrate_ring() {
+        let isl = |base: f64| -> Vec<(Coeffs, f64)> {
+            (0..4).map(|i| ([base; 30], base + i as f64)).collect()
+        };
+        let mut islands = vec![isl(0.0), isl(10.0), isl(20.0)];
+        migrate(&mut islands, 2);
+        let fit = |i: usize| -> Vec<f64> {
+            islands[i].iter().map(|p| p.1).collect()
+        };
+        // Island 0 receives island 2's best two.
+        assert_eq!(fit(0), [3.0, 2.0, 23.0, 22.0]);
+        assert_eq!(fit(1), [13.0, 12.0, 3.0, 2.0]);
+        assert_eq!(fit(2), [23.0, 22.0, 13.0, 12.0]);
+        assert_eq!(islands[0][2].0, [20.0; 30]);
+    }
+
+    #[test]
+    fn same_seed_same_result() {
+        let (mut a, mut b) = ([0.0; 30], [0.0; 30]);
+        mutate(&mut a, 0.3, &mut rng(1));
+        mutate(&mut b, 0.3, &mut rng(1));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn sparse_has_k_terms() {
+        let mut r = rng(3);
+        for k in [0, 5, 30, 40] {
+            let c = rand_coeffs_sparse(&mut r, k);
+            let nnz = c.iter().filter(|v| **v != 0.0).count();
+            assert_eq!(nnz, k.min(30));
+            assert!(c.iter().all(|v| v.abs() <= COEFF_RANGE));
+        }
+    }
+
+    #[test]
+    fn mutate_stays_in_bounds() {
+        for seed in SEEDS {
+            let mut r = rng(seed);
+            let mut c = rand_coeffs(&mut r);
+            mutate(&mut c, 5.0, &mut r);
+            assert_eq!(c.len(), crate::ode::NCOEFFS);
+            assert!(c.iter().all(|v| v.abs() <= COEFF_RANGE));
+        }
+    }
+
+    #[test]
+    fn crossover_genes_from_parents() {
+        for seed in SEEDS {
+            let mut r = rng(seed);
+            let (a, b) = (rand_coeffs(&mut r), rand_coeffs(&mut r));
+            let c = crossover(&a, &b, &mut r);
+            for i in 0..c.len() {
+                assert!(c[i] == a[i] || c[i] == b[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn select_keeps_elite() {
+        for seed in SEEDS {
+            let mut r = rng(seed);
+            let mut pop: Vec<(Coeffs, f64)> = (0..9)
+                .map(|_| (rand_coeffs(&mut r), r.gen()))
+                .collect();
+            let best = pop
+                .iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .copied()
+                .unwrap();
+            select(&mut pop, 0.3, 0.5, 3, &CoeffMask::default(), &mut r);
+            assert_eq!(pop[0], best);
+        }
+    }
+
+    #[test]
+    fn tournament_pressure() {
+        let mut r = rng(3);
+        let pop: Vec<(Coeffs, f64)> =
+            (0..8).map(|i| ([0.25 * i as f64; 30], i as f64)).collect();
+        // A full-size tournament always finds the best.
+        let mut p = pop.clone();
+        select(&mut p, 0.0, 0.0, 64, &CoeffMask::default(), &mut r);
+        assert!(p.iter().all(|(c, _)| c[0] == 1.75));
+        // Size 1 is uniform: children stay diverse.
+        let mut p = pop.clone();
+        select(&mut p, 0.0, 1.0, 1, &CoeffMask::default(), &mut r);
+        assert_eq!(p[0], pop[7]);
+        assert!(p[1..].iter().all(|x| x.1 == f64::NEG_INFINITY));
+        let mixed = p[1..].iter().any(|(c, _)| c[0] != c[29]);
+        assert!(mixed, "no child mixed two parents");
+    }
+
+    #[test]
+    fn mask_pins_children() {
+        let mask = CoeffMask::origin_equilibrium().freeze(4, 1.5);
+        let mut r = rng(7);
+        let mut pop: Vec<(Coeffs, f64)> = (0..6)
+            .map(|i| {
+                let mut c = rand_coeffs(&mut r);
+                mask.apply(&mut c);
+                (c, i as f64)
+            })
+            .collect();
+        select(&mut pop, 0.5, 0.5, 2, &mask, &mut r);
+        for (c, _) in &pop {
+            assert_eq!([c[0], c[10], c[20], c[4]], [0.0, 0.0, 0.0, 1.5]);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// Known chaotic flows as coefficient vectors: test
+// fixtures and search seeds beyond Lorenz.
+
+pub use crate::ode::lorenz_coeffs;
+use crate::ode::{Coeffs, NCOEFFS, NTERMS};
+
+/// Basis indices, in ode.rs term order.
+const ONE: usize = 0;
+const X: usize = 1;
+const Y: usize = 2;
+const Z: usize = 3;
+const XX: usize = 4;
+const YY: usize = 5;
+const ZZ: usize = 6;
+const XY: usize = 7;
+const XZ: usize = 8;
+const YZ: usize = 9;
+
+type Terms = &'static [(usize, f64)];
+
+/// Build from (term, coefficient) lists per equation.
+fn from_terms(eqs: [Terms; 3]) -> Coeffs {
+    let mut c = [0.0; NCOEFFS];
+    for (eq, terms) in eqs.iter().enumerate() {
+        for &(t, v) in *terms {
+            c[eq * NTERMS + t] = v;
+        }
+    }
+    c
+}
+
+/// Sprott's minimal chaotic flows A-S (Phys. Rev. E
+/// 50, 1994), by letter in either case. A is
+/// conservative; the rest are dissipative.
+pub fn sprott(letter: char) -> Option<Coeffs> {
+    let eqs: [Terms; 3] = match letter.to_ascii_uppercase() {
+        'A' => [&[(Y, 1.0)], &[(X, -1.0), (YZ, 1.0)], &[
+            (ONE, 1.0),
+            (YY, -1.0),
+        ]],
+        'B' => [&[(YZ, 1.0)], &[(X, 1.0), (Y, -1.0)], &[
+            (ONE, 1.0),
+            (XY, -1.0),
+        ]],
+        'C' => [&[(YZ, 1.0)], &[(X, 1.0), (Y, -1.0)], &[
+            (ONE, 1.0),
+            (XX, -1.0),
+        ]],
+        'D' => [&[(Y, -1.0)], &[(X, 1.0), (Z, 1.0)], &[
+            (XZ, 1.0),
+            (YY, 3.0),
+        ]],
+        'E' => [&[(YZ, 1.0)], &[(XX, 1.0), (Y, -1.0)], &[
+            (ONE, 1.0),
+            (X, -4.0),
+        ]],
+        'F' => [&[(Y, 1.0), (Z, 1.0)], &[(X, -1.0), (Y, 0.5)], &[
+            (XX, 1.0),
+            (Z, -1.0),
+        ]],
+        'G' => [&[(X, 0.4), (Z, 1.0)], &[(XZ, 1.0), (Y, -1.0)], &[
+            (X, -1.0),
+            (Y, 1.0),
+        ]],
+        'H' => [&[(Y, -1.0), (ZZ, 1.0)], &[(X, 1.0), (Y, 0.5)], &[
+            (X, 1.0),
+            (Z, -1.0),
+        ]],
+        'I' => [&[(Y, -0.2)], &[(X, 1.0), (Z, 1.0)], &[
+            (X, 1.0),
+            (YY, 1.0),
+            (Z, -1.0),
+        ]],
+        'J' => [&[(Z, 2.0)], &[(Y, -2.0), (Z, 1.0)], &[
+            (X, -1.0),
+            (Y, 1.0),
+            (YY, 1.0),
+        ]],
+        'K' => [&[(XY, 1.0), (Z, -1.0)], &[(X, 1.0), (Y, -1.0)], &[
+            (X, 1.0),
+            (Z, 0.3),
+        ]],
+        'L' => [&[(Y, 1.0), (Z, 3.9)], &[(XX, 0.9), (Y, -1.0)], &[
+            (ONE, 1.0),
+            (X, -1.0),
+        ]],
+        'M' => [&[(Z, -1.0)], &[(XX, -1.0), (Y, -1.0)], &[
+            (ONE, 1.7),
+            (X, 1.7),
+            (Y, 1.0),
+        ]],
+        'N' => [&[(Y, -2.0)], &[(X, 1.0), (ZZ, 1.0)], &[
+            (ONE, 1.0),
+            (Y, 1.0),
+            (Z, -2.0),
+        ]],
+        'O' => [&[(Y, 1.0)], &[(X, 1.0), (Z, -1.0)], &[
+            (X, 1.0),
+            (XZ, 1.0),
+            (Y, 2.7),
+        ]],
+        'P' => [&[(Y, 2.7), (Z, 1.0)], &[(X, -1.0), (YY, 1.0)], &[
+            (X, 1.0),
+            (Y, 1.0),
+        ]],
+        'Q' => [&[(Z, -1.0)], &[(X, 1.0), (Y, -1.0)], &[
+            (X, 3.1),
+            (YY, 1.0),
+            (Z, 0.5),
+        ]],
+        'R' => [&[(ONE, 0.9), (Y, -1.0)], &[(ONE, 0.4), (Z, 1.0)], &[
+            (XY, 1.0),
+            (Z, -1.0),
+        ]],
+        'S' => [&[(X, -1.0), (Y, -4.0)], &[(X, 1.0), (ZZ, 1.0)], &[
+            (ONE, 1.0),
+            (X, 1.0),
+        ]],
+        _ => return None,
+    };
+    Some(from_terms(eqs))
+}
+
+/// Sprott A (Nosé-Hoover): x' = y, y' = -x + yz,
+/// z' = 1 - y².
+pub fn sprott_a() -> Coeffs {
+    sprott('A').unwrap()
+}
+
+/// Sprott B: x' = yz, y' = x - y, z' = 1 - xy.
+pub fn sprott_b() -> Coeffs {
+    sprott('B').unwrap()
+}
+
+/// Rössler: x' = -y - z, y' = x + ay,
+/// z' = b + z(x - c). Chaotic at (0.2, 0.2, 5.7).
+pub fn rossler_coeffs(a: f64, b: f64, c: f64) -> Coeffs {
+    let mut k = [0.0; NCOEFFS];
+    k[Y] = -1.0;
+    k[Z] = -1.0;
+    k[NTERMS + X] = 1.0;
+    k[NTERMS + Y] = a;
+    k[2 * NTERMS + ONE] = b;
+    k[2 * NTERMS + Z] = -c;
+    k[2 * NTERMS + XZ] = 1.0;
+    k
+}
+
+/// Chen: x' = a(y - x), y' = (c - a)x - xz + cy,
+/// z' = xy - bz. Chaotic at (35, 3, 28).
+pub fn chen_coeffs(a: f64, b: f64, c: f64) -> Coeffs {
+    let mut k = [0.0; NCOEFFS];
+    k[X] = -a;
+    k[Y] = a;
+    k[NTERMS + X] = c - a;
+    k[NTERMS + Y] = c;
+    k[NTERMS + XZ] = -1.0;
+    k[2 * NTERMS + Z] = -b;
+    k[2 * NTERMS + XY] = 1.0;
+    k
+}
+
+/// Lorenz-84: x' = -y² - z² - ax + aF,
+/// y' = xy - bxz - y + G, z' = bxy + xz - z.
+/// Chaotic at (0.25, 4, 8, 1); an attracting
+/// 2-torus at (0.25, 4, 6.8, 1.36).
+pub fn lorenz84_coeffs(a: f64, b: f64, f: f64, g: f64) -> Coeffs {
+    let mut k = [0.0; NCOEFFS];
+    k[ONE] = a * f;
+    k[X] = -a;
+    k[YY] = -1.0;
+    k[ZZ] = -1.0;
+    k[NTERMS + ONE] = g;
+    k[NTERMS + Y] = -1.0;
+    k[NTERMS + XY] = 1.0;
+    k[NTERMS + XZ] = -b;
+    k[2 * NTERMS + Z] = -1.0;
+    k[2 * NTERMS + XY] = b;
+    k[2 * NTERMS + XZ] = 1.0;
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::{classify, Dynamics};
+
+    #[test]
+    fn presets_are_chaotic() {
+        let mut all: Vec<(String, Coeffs)> = ('B'..='S')
+            .map(|l| (l.to_string(), sprott(l).unwrap()))
+            .collect();
+        all.push(("rossler".into(), rossler_coeffs(0.2, 0.2, 5.7)));
+        all.push(("chen".into(), chen_coeffs(35.0, 3.0, 28.0)));
+        for (name, c) in &all {
+            let d = classify(c);
+            assert!(matches!(d, Dynamics::Chaotic(_)), "{name}: {d:?}");
+        }
+        // Conservative: λ1 ~ 0.014 sits at the
+        // threshold, so only ask that it stays bounded.
+        let d = classify(&sprott_a());
+        assert!(!matches!(d, Dynamics::Divergent { .. }), "A: {d:?}");
+        assert_eq!(sprott('b'), Some(sprott_b()));
+        assert!(sprott('T').is_none());
+    }
+
+    #[test]
+    fn rossler_weak_band_chaos() {
+        // λ1 ~ 0.07, an order above CHAOS_THRESH
+        // but ten times below Lorenz.
+        match classify(&rossler_coeffs(0.2, 0.2, 5.7)) {
+            Dynamics::Chaotic(d) => {
+                let l1 = d.spectrum[0];
+                assert!((0.05..0.1).contains(&l1), "λ1={l1}");
+                assert!((2.0..2.05).contains(&d.ky_dim), "{d:?}");
+            }
+            other => panic!("expected chaotic, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+// PPM density renderer for attractor point clouds.
+// Orthographic or perspective projection,
+// log-scaled hit counts.
+
+use crate::lyapunov::{cross, dot, norm, scale, sub};
+use crate::ode::State;
+use std::io::Write;
+use std::path::Path;
+
+/// 8-bit RGB image, row-major.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub w: usize,
+    pub h: usize,
+    pub rgb: Vec<u8>,
+}
+
+impl Image {
+    /// Write as binary PPM (P6).
+    pub fn write_ppm(
+        &self,
+        path: &Path,
+    ) -> std::io::Result<()> {
+        let f = std::fs::File::create(path)?;
+        let mut w = std::io::BufWriter::new(f);
+        write!(w, "P6\n{} {}\n255\n", self.w, self.h)?;
+        w.write_all(&self.rgb)?;
+        w.flush()
+    }
+}
+
+/// Camera orientation: yaw about z, then pitch
+/// about the rotated x axis (radians).
+#[derive(Debug, Clone, Copy)]
+pub struct View {
+    pub yaw: f64,
+    pub pitch: f64,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View { yaw: 0.6, pitch: 0.4 }
+    }
+}
+
+/// Rotate p into camera frame: (right, up, depth).
+#[inline]
+fn to_camera(p: &State, v: &View) -> State {
+    let (sy, cy) = v.yaw.sin_cos();
+    let (sp, cp) = v.pitch.sin_cos();
+    let x = cy * p[0] - sy * p[1];
+    let y = sy * p[0] + cy * p[1];
+    [x, cp * p[2] - sp * y, sp * p[2] + cp * y]
+}
+
+/// Bounding sphere (centre, radius): framing that
+/// is identical for every view direction.
+fn bounds(traj: &[State]) -> (State, f64) {
+    let n = traj.len().max(1) as f64;
+    let mut c = [0.0; 3];
+    for p in traj {
+        for (ci, pi) in c.iter_mut().zip(p) {
+            *ci += pi / n;
+        }
+    }
+    let r = traj
+        .iter()
+        .map(|p| {
+            let d = [p[0] - c[0], p[1] - c[1], p[2] - c[2]];
+            (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+        })
+        .fold(0.0f64, f64::max);
+    (c, if r > 0.0 { r } else { 1.0 })
+}
+
+/// Pixel index of p framed by the sphere (c, r).
+#[inline]
+fn pixel(
+    p: &State,
+    (c, r): (State, f64),
+    v: &View,
+    size: usize,
+) -> Option<usize> {
+    let half = 0.5 * size as f64;
+    let q = [p[0] - c[0], p[1] - c[1], p[2] - c[2]];
+    let s = to_camera(&q, v);
+    let px = half + s[0] / r * half * 0.95;
+    let py = half - s[1] / r * half * 0.95;
+    if px < 0.0 || py < 0.0 {
+        return None;
+    }
+    let (ix, iy) = (px as usize, py as usize);
+    (ix < size && iy < size).then_some(iy * size + ix)
+}
+
+/// Per-pixel hit counts for one view.
+fn density(
+    traj: &[State],
+    v: &View,
+    size: usize,
+) -> Vec<f64> {
+    let b = bounds(traj);
+    let mut d = vec![0.0; size * size];
+    for p in traj {
+        if let Some(i) = pixel(p, b, v, size) {
+            d[i] += 1.0;
+        }
+    }
+    d
+}
+
+/// Density render coloured by a per-point value:
+/// pixel hue runs blue (low) to red (high) by the
+/// mean value there, brightness by hit count.
+pub fn render_scalar(
+    pts: &[(State, f64)],
+    v: &View,
+    size: usize,
+) -> Image {
+    let traj: Vec<State> = pts.iter().map(|p| p.0).collect();
+    let b = bounds(&traj);
+    let mut d = vec![0.0; size * size];
+    let mut sum = vec![0.0; size * size];
+    for (p, x) in pts {
+        if let Some(i) = pixel(p, b, v, size) {
+            d[i] += 1.0;
+            sum[i] += x;
+        }
+    }
+    let (lo, hi) = pts.iter().fold((f64::MAX, f64::MIN), |(a, b), p| {
+        (a.min(p.1), b.max(p.1))
+    });
+    let span = if hi > lo { hi - lo } else { 1.0 };
+    let g = tone(&d);
+    let mut rgb = Vec::with_capacity(3 * size * size);
+    for ((&n, &s), &g) in d.iter().zip(&sum).zip(&g) {
+        let t = if n > 0.0 { (s / n - lo) / span } else { 0.0 };
+        let g = g as f64;
+        rgb.extend([t * g, 0.3 * g, (1.0 - t) * g].map(|c| c as u8));
+    }
+    Image { w: size, h: size, rgb }
+}
+
+/// Pinhole camera at `eye` looking at the
+/// trajectory's centre; `fov` is the full vertical
+/// field of view in radians.
+#[derive(Debug, Clone, Copy)]
+pub struct Perspective {
+    pub eye: State,
+    pub fov: f64,
+}
+
+impl Perspective {
+    /// Camera three bounding radii out along -y,
+    /// slightly raised, with a 45° field of view.
+    pub fn auto(traj: &[State]) -> Self {
+        let (c, r) = bounds(traj);
+        Perspective {
+            eye: [c[0], c[1] - 3.0 * r, c[2] + r],
+            fov: 45f64.to_radians(),
+        }
+    }
+}
+
+fn unit(a: State) -> State {
+    scale(&a, 1.0 / norm(&a))
+}
+
+/// Nearest depth still drawn (clip plane).
+const NEAR: f64 = 1e-6;
+
+/// Camera basis (right, up, forward) aimed at `at`.
+fn basis(eye: &State, at: &State) -> [State; 3] {
+    let f = unit(sub(at, eye));
+    // World z is up unless looking along it.
+    let up = if f[2].abs() > 0.99 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 0.0, 1.0]
+    };
+    let r = unit(cross(&f, &up));
+    [r, cross(&r, &f), f]
+}
+
+/// Project p to normalized screen coordinates
+/// (±1 spans the fov) and depth; None if behind
+/// the near plane.
+fn project(
+    p: &State,
+    eye: &State,
+    b: &[State; 3],
+    tan: f64,
+) -> Option<(f64, f64, f64)> {
+    let q = sub(p, eye);
+    let d = dot(&q, &b[2]);
+    if d <= NEAR {
+        return None;
+    }
+    let k = 1.0 / (d * tan);
+    Some((dot(&q, &b[0]) * k, dot(&q, &b[1]) * k, d))
+}
+
+/// Depth-cued hit weights: each point adds
+/// (d0 / d)^2, d0 the eye-to-centre distance.
+fn density_perspective(
+    traj: &[State],
+    cam: &Perspective,
+    size: usize,
+) -> Vec<f64> {
+    let (c, _) = bounds(traj);
+    let b = basis(&cam.eye, &c);
+    let d0 = dot(&sub(&c, &cam.eye), &b[2]);
+    let tan = (0.5 * cam.fov).tan();
+    let half = 0.5 * size as f64;
+    let mut d = vec![0.0; size * size];
+    for p in traj {
+        let Some((x, y, z)) = project(p, &cam.eye, &b, tan) else {
+            continue;
+        };
+        let (px, py) = (half + x * half, half - y * half);
+        if px < 0.0 || py < 0.0 {
+            continue;
+        }
+        let (ix, iy) = (px as usize, py as usize);
+        if ix < size && iy < size {
+            d[iy * size + ix] += (d0 / z).powi(2);
+        }
+    }
+    d
+}
+
+/// Greyscale perspective render with depth cueing.
+pub fn render_perspective(
+    traj: &[State],
+    cam: &Perspective,
+    size: usize,
+) -> Image {
+    let g = tone(&density_perspective(traj, cam, size));
+    let rgb = g.iter().flat_map(|&x| [x, x, x]).collect();
+    Image { w: size, h: size, rgb }
+}
+
+/// Log tone-map counts to 0..=255.
+fn tone(d: &[f64]) -> Vec<u8> {
+    let max = d.iter().fold(0.0f64, |a, &b| a.max(b));
+    let k = (1.0 + max).ln();
+    d.iter()
+        .map(|&v| {
+            if k > 0.0 {
+                (255.0 * (1.0 + v).ln() / k) as u8
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/// Greyscale density render.
+pub fn render(
+    traj: &[State],
+    v: &View,
+    size: usize,
+) -> Image {
+    let g = tone(&density(traj, v, size));
+    let rgb = g.iter().flat_map(|&x| [x, x, x]).collect();
+    Image { w: size, h: size, rgb }
+}
+
+/// Red-cyan anaglyph: left eye in red, right eye
+/// in green+blue, eyes `sep` radians apart in yaw.
+pub fn render_anaglyph(
+    traj: &[State],
+    v: &View,
+    size: usize,
+    sep: f64,
+) -> Image {
+    let eye = |dy: f64| View { yaw: v.yaw + dy, ..*v };
+    let l = tone(&density(traj, &eye(-0.5 * sep), size));
+    let r = tone(&density(traj, &eye(0.5 * sep), size));
+    let rgb = l
+        .iter()
+        .zip(&r)
+        .flat_map(|(&a, &b)| [a, b, b])
+        .collect();
+    Image { w: size, h: size, rgb }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::{integrate_traj, lorenz_coeffs};
+
+    #[test]
+    fn anaglyph_eyes_differ() {
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let traj =
+            integrate_traj(&c, &[1.0, 1.0, 1.0], 0.01, 5000);
+        let img = render_anaglyph(
+            &traj,
+            &View::default(),
+            64,
+            0.1,
+        );
+        assert_eq!(img.rgb.len(), 64 * 64 * 3);
+        let differ = img
+            .rgb
+            .chunks(3)
+            .any(|p| p[0] != p[1]);
+        assert!(differ, "eye views identical");
+        assert!(img.rgb.chunks(3).all(|p| p[1] == p[2]));
+    }
+
+    #[test]
+    fn perspective_depth_and_clip() {
+        // Camera on -y looking at the origin.
+        let cam = Perspective {
+            eye: [0.0, -10.0, 0.0],
+            fov: 1.0,
+        };
+        let near = [-1.0, -5.0, 0.0];
+        let far = [1.0, 5.0, 0.0];
+        let behind = [0.0, -20.0, 0.0];
+        let traj = [near, far, behind, [0.0; 3]];
+        let d = density_perspective(&traj, &cam, 64);
+        let lit: Vec<f64> =
+            d.iter().copied().filter(|&v| v > 0.0).collect();
+        // Behind point clipped: 3 lit pixels remain.
+        assert_eq!(lit.len(), 3);
+        let max = lit.iter().fold(0.0f64, |a, &b| a.max(b));
+        let min = lit.iter().fold(f64::MAX, |a, &b| a.min(b));
+        assert!(max > 2.0 * min, "no depth cue: {lit:?}");
+    }
+
+    #[test]
+    fn scalar_colour_follows_value() {
+        let pts = [([-1.0, 0.0, 0.0], 0.0), ([1.0, 0.0, 0.0], 1.0)];
+        let v = View { yaw: 0.0, pitch: 0.0 };
+        let img = render_scalar(&pts, &v, 16);
+        let lit: Vec<&[u8]> =
+            img.rgb.chunks(3).filter(|p| p[1] > 0).collect();
+        assert_eq!(lit.len(), 2);
+        // Left (low) is blue, right (high) is red.
+        assert!(lit[0][2] > lit[0][0] && lit[1][0] > lit[1][2]);
+    }
+}
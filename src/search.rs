@@ -1,208 +1,1459 @@
-// Search strategies: random + evolutionary.
-
-use crate::catalog::{self, Entry};
-use crate::classify::{self, Dynamics};
-use crate::ode::{self, Coeffs, NCOEFFS};
-use rand::Rng;
-use std::path::Path;
-
-const COEFF_RANGE: f64 = 2.0;
-const TRAJ_SAMPLE: usize = 50000;
-const TRAJ_DT: f64 = 0.01;
-
-/// Random coefficient vector in [-range, range].
-fn rand_coeffs(rng: &mut impl Rng) -> Coeffs {
-    let mut c = [0.0; NCOEFFS];
-    for v in c.iter_mut() {
-        *v = rng.gen_range(
-            -COEFF_RANGE..COEFF_RANGE,
-        );
-    }
-    c
-}
-
-/// Report a chaotic find to stdout and catalog.
-fn report(
-    c: &Coeffs,
-    data: &crate::lyapunov::LyapData,
-    results_dir: &Path,
-    found: usize,
-    method: &str,
-) {
-    let hash = catalog::coeff_hash(c);
-    println!(
-        "  CHAOTIC #{}: hash={:016x} \
-         λ1={:.4} dim={:.3}",
-        found, hash, data.spectrum[0], data.ky_dim,
-    );
-    // Sample trajectory for catalog.
-    let s0 = [0.1, 0.1, 0.1];
-    let traj = ode::integrate_traj(
-        c, &s0, TRAJ_DT, TRAJ_SAMPLE,
-    );
-    let entry = Entry::new(
-        c,
-        data.spectrum,
-        data.ky_dim,
-        &traj,
-        method,
-    );
-    if let Err(e) = catalog::save(results_dir, &entry)
-    {
-        eprintln!("  save error: {e}");
-    }
-}
-
-/// Random search: evaluate `count` random systems.
-pub fn random_search(
-    count: usize,
-    results_dir: &Path,
-) {
-    let mut rng = rand::thread_rng();
-    let mut found = 0usize;
-    let mut evaluated = 0usize;
-    println!("Random search: {count} systems");
-    for i in 0..count {
-        let c = rand_coeffs(&mut rng);
-        evaluated += 1;
-        match classify::classify(&c) {
-            Dynamics::Chaotic(data) => {
-                found += 1;
-                report(
-                    &c,
-                    &data,
-                    results_dir,
-                    found,
-                    "random",
-                );
-            }
-            _ => {}
-        }
-        if (i + 1) % 1000 == 0 {
-            println!(
-                "  [{}/{}] chaotic: {} ({:.2}%)",
-                i + 1,
-                count,
-                found,
-                100.0 * found as f64
-                    / evaluated as f64,
-            );
-        }
-    }
-    println!(
-        "Done. {evaluated} evaluated, \
-         {found} chaotic ({:.2}%)",
-        100.0 * found as f64 / evaluated as f64,
-    );
-}
-
-/// Evolutionary search.
-pub fn evolve_search(
-    generations: usize,
-    pop_size: usize,
-    results_dir: &Path,
-) {
-    let mut rng = rand::thread_rng();
-    let mut pop: Vec<(Coeffs, f64)> = (0..pop_size)
-        .map(|_| (rand_coeffs(&mut rng), f64::NEG_INFINITY))
-        .collect();
-    let mut found = 0usize;
-    let mut total_eval = 0usize;
-    let mutate_std = 0.3;
-
-    println!(
-        "Evolve: {generations} gens, pop {pop_size}"
-    );
-
-    for gen in 0..generations {
-        // Evaluate fitness for new individuals.
-        for item in pop.iter_mut() {
-            if item.1 == f64::NEG_INFINITY {
-                total_eval += 1;
-                item.1 = fitness(&item.0);
-                if let Dynamics::Chaotic(data) =
-                    classify::classify(&item.0)
-                {
-                    found += 1;
-                    report(
-                        &item.0,
-                        &data,
-                        results_dir,
-                        found,
-                        "evolve",
-                    );
-                }
-            }
-        }
-
-        // Sort by fitness descending.
-        pop.sort_by(|a, b| {
-            b.1.partial_cmp(&a.1)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        if (gen + 1) % 50 == 0 {
-            let best = pop[0].1;
-            println!(
-                "  gen {}: best_fit={:.4} \
-                 chaotic={found} eval={total_eval}",
-                gen + 1,
-                best,
-            );
-        }
-
-        // Keep top half, mutate to fill rest.
-        let half = pop_size / 2;
-        for i in half..pop_size {
-            let parent = i % half;
-            let mut child = pop[parent].0;
-            mutate(
-                &mut child,
-                mutate_std,
-                &mut rng,
-            );
-            pop[i] = (child, f64::NEG_INFINITY);
-        }
-    }
-    println!(
-        "Done. {total_eval} evaluated, \
-         {found} chaotic",
-    );
-}
-
-/// Fitness: higher = more interesting.
-/// Positive Lyapunov + bounded = best.
-fn fitness(c: &Coeffs) -> f64 {
-    let s0 = [0.1f64, 0.1, 0.1];
-    let (lyap, ok) = crate::lyapunov::max_lyapunov(
-        c, &s0, 0.005, 1000, 5000,
-    );
-    if !ok || !lyap.is_finite() {
-        return -100.0;
-    }
-    // Reward positive exponent, penalize huge.
-    if lyap > 0.0 && lyap < 10.0 {
-        lyap
-    } else if lyap <= 0.0 {
-        lyap - 10.0
-    } else {
-        -50.0
-    }
-}
-
-/// Mutate coefficients with Gaussian noise.
-fn mutate(
-    c: &mut Coeffs,
-    std: f64,
-    rng: &mut impl Rng,
-) {
-    for v in c.iter_mut() {
-        // Box-Muller for normal dist w/o dep.
-        let u1: f64 = rng.gen_range(1e-10..1.0);
-        let u2: f64 = rng.gen_range(0.0..
-            std::f64::consts::TAU);
-        let z = (-2.0 * u1.ln()).sqrt() * u2.cos();
-        *v += std * z;
-        *v = v.clamp(-COEFF_RANGE, COEFF_RANGE);
-    }
-}
+// Search strategies: random + evolutionary.
+
+use crate::analysis;
+use crate::catalog::{self, Entry};
+use crate::classify::{self, ClassifyConfig, Dynamics};
+use crate::lyapunov::LyapData;
+use crate::ode::{self, Coeffs, State};
+use crate::novelty::{self, NoveltyArchive};
+use crate::observer::{GenStats, SearchObserver};
+use crate::ops::{self, rand_coeffs, rand_coeffs_sparse, CoeffMask};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+const TRAJ_SAMPLE: usize = 50000;
+pub const TRAJ_DT: f64 = 0.01;
+/// Coefficients at or below this count as absent.
+const SPARSE_TOL: f64 = 1e-6;
+
+/// How multi-start fitness scores combine.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Agg {
+    #[default]
+    Min,
+    Mean,
+    Median,
+}
+
+impl FromStr for Agg {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "min" => Ok(Agg::Min),
+            "mean" => Ok(Agg::Mean),
+            "median" => Ok(Agg::Median),
+            _ => Err(format!(
+                "unknown aggregate {s} (expected min, mean or median)"
+            )),
+        }
+    }
+}
+
+/// Evolutionary fitness options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FitnessOpts {
+    /// Initial conditions per candidate.
+    pub starts: usize,
+    pub agg: Agg,
+    /// Subtracted per nonzero coefficient.
+    pub sparsity_penalty: f64,
+    /// Weight of the novelty bonus (0 disables).
+    pub novelty_weight: f64,
+    /// Neighbours in the novelty distance.
+    pub novelty_k: usize,
+}
+
+impl Default for FitnessOpts {
+    fn default() -> Self {
+        FitnessOpts {
+            starts: 1,
+            agg: Agg::Min,
+            sparsity_penalty: 0.0,
+            novelty_weight: 0.0,
+            novelty_k: 5,
+        }
+    }
+}
+
+/// Mutation std decaying geometrically from
+/// `start` at the first generation to `end` at
+/// the last.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MutateSchedule {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Default for MutateSchedule {
+    fn default() -> Self {
+        MutateSchedule { start: 0.3, end: 0.3 }
+    }
+}
+
+impl MutateSchedule {
+    /// Std for generation `gen` of `gens`.
+    pub fn std_at(&self, gen: usize, gens: usize) -> f64 {
+        if gens < 2 || self.start <= 0.0 || self.end <= 0.0
+        {
+            return self.start;
+        }
+        let t = gen as f64 / (gens - 1) as f64;
+        self.start * (self.end / self.start).powf(t)
+    }
+}
+
+/// Output level: quiet prints only the seed and
+/// final summary; debug logs every candidate.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq,
+    PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+    Debug,
+}
+
+impl Verbosity {
+    /// Whether a message at `level` is emitted.
+    pub fn shows(self, level: Verbosity) -> bool {
+        level <= self
+    }
+}
+
+/// Acceptance band for saving finds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Band {
+    pub min_dim: Option<f64>,
+    pub max_dim: Option<f64>,
+    pub min_lambda1: Option<f64>,
+    pub max_lambda1: Option<f64>,
+    pub unstable_dim: Option<usize>,
+}
+
+impl Band {
+    /// Whether a chaotic find lies inside the band.
+    pub fn accepts(&self, d: &LyapData) -> bool {
+        let within = |v: f64, lo: Option<f64>, hi: Option<f64>| {
+            lo.is_none_or(|l| v >= l) && hi.is_none_or(|h| v <= h)
+        };
+        within(d.ky_dim, self.min_dim, self.max_dim)
+            && within(
+                d.spectrum[0],
+                self.min_lambda1,
+                self.max_lambda1,
+            )
+            && self
+                .unstable_dim
+                .is_none_or(|k| d.unstable_dim == k)
+    }
+}
+
+/// Options shared by all search strategies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOpts {
+    /// Evaluate in canonical time units.
+    pub canonical: bool,
+    pub classify: ClassifyConfig,
+    pub fitness: FitnessOpts,
+    pub mutate: MutateSchedule,
+    pub band: Band,
+    pub verbosity: Verbosity,
+    /// Candidates per parallel task (0 means 1).
+    pub chunk_size: usize,
+    /// Minimum time between catalog writes.
+    pub write_interval_ms: u64,
+    /// Fraction of evolve children bred by
+    /// crossover rather than cloned.
+    pub crossover_rate: f64,
+    /// Individuals per parent tournament.
+    pub tournament_size: usize,
+    /// RNG seed; None draws one from entropy.
+    pub seed: Option<u64>,
+    /// Evolve checkpoint period in generations
+    /// (0 disables).
+    pub checkpoint_every: usize,
+    /// Continue evolve from its checkpoint.
+    pub resume: bool,
+    /// Nonzero coefficients per random candidate
+    /// (None: all).
+    pub sparsity: Option<usize>,
+    /// Start evolve from the catalog's best entries
+    /// by λ1, topped up with random genomes.
+    pub seed_from_catalog: bool,
+    /// Write finds gzip-compressed.
+    pub compress: bool,
+    /// Coefficients pinned in every candidate.
+    pub mask: CoeffMask,
+    /// Stored trajectory points per find, thinned
+    /// by arc length (None keeps the time series
+    /// that psd and time export expect).
+    pub store_points: Option<usize>,
+}
+
+impl Default for SearchOpts {
+    fn default() -> Self {
+        SearchOpts {
+            canonical: false,
+            classify: ClassifyConfig::default(),
+            fitness: FitnessOpts::default(),
+            mutate: MutateSchedule::default(),
+            band: Band::default(),
+            verbosity: Verbosity::Normal,
+            chunk_size: 1,
+            write_interval_ms: 0,
+            crossover_rate: 0.0,
+            tournament_size: 3,
+            seed: None,
+            checkpoint_every: 10,
+            resume: false,
+            sparsity: None,
+            seed_from_catalog: false,
+            compress: false,
+            mask: CoeffMask::default(),
+            store_points: None,
+        }
+    }
+}
+
+/// Coefficients to evaluate plus their time scale.
+fn prepare(c: &Coeffs, opts: &SearchOpts) -> (Coeffs, f64) {
+    if opts.canonical {
+        ode::canonicalize(c)
+    } else {
+        (*c, 1.0)
+    }
+}
+
+/// Catalog entry with a sampled trajectory, its
+/// initial transient trimmed.
+pub fn make_entry(
+    c: &Coeffs,
+    data: &LyapData,
+    method: &str,
+) -> Entry {
+    let full = ode::integrate_traj(
+        c, &catalog::TRAJ_S0, TRAJ_DT, TRAJ_SAMPLE,
+    );
+    let traj = analysis::trim_transient(&full, TRAJ_DT);
+    let mut e = Entry::new(
+        c,
+        data.spectrum,
+        data.ky_dim,
+        traj,
+        method,
+    );
+    e.topo_entropy = analysis::entropy_estimate(traj);
+    e.frenet = Some(analysis::frenet_stats(c, traj));
+    e.pose = Some(analysis::canonical_pose(traj).1);
+    e
+}
+
+/// What a search did, for callers that want more
+/// than the printed log.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchSummary {
+    /// Systems classified (cache hits excluded).
+    pub evaluated: usize,
+    /// Chaotic finds, including earlier sessions of
+    /// a resumed run.
+    pub found: usize,
+    /// Hashes of this session's chaotic finds, in
+    /// report order.
+    pub chaotic_ids: Vec<u64>,
+    pub elapsed: Duration,
+    /// Wall time classifying and, in evolve,
+    /// scoring candidates.
+    pub classify_time: Duration,
+    /// Wall time writing the catalog and
+    /// checkpoints.
+    pub io_time: Duration,
+    /// Best fitness after each generation this
+    /// session (empty for random search).
+    pub best_fitness: Vec<f64>,
+}
+
+impl SearchSummary {
+    /// Evaluations per wall-clock second.
+    pub fn rate(&self) -> f64 {
+        self.evaluated as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+/// Where finds go: the catalog writer plus the
+/// observer, with the running find count.
+struct Sink<'a> {
+    writer: catalog::Writer,
+    obs: &'a mut dyn SearchObserver,
+    found: usize,
+    ids: Vec<u64>,
+    /// Time spent in catalog writes.
+    io: Duration,
+}
+
+impl Sink<'_> {
+    /// Report a chaotic find and, if it is inside
+    /// the band, queue it for the catalog. Returns
+    /// whether it was queued; on_chaotic_found
+    /// fires once it is on disk.
+    fn report(
+        &mut self,
+        c: &Coeffs,
+        scale: f64,
+        data: &LyapData,
+        method: &str,
+        opts: &SearchOpts,
+    ) -> bool {
+        self.found += 1;
+        let id = catalog::coeff_hash(c);
+        self.ids.push(id);
+        let saved = opts.band.accepts(data) && {
+            let mut entry = make_entry(c, data, method);
+            entry.time_scale = scale;
+            entry.seed = opts.seed;
+            if let Some(n) = opts.store_points {
+                entry.trajectory =
+                    analysis::downsample_arclength(&entry.trajectory, n);
+                entry.resampled = true;
+            }
+            let t = Instant::now();
+            let pushed = self.writer.push(entry);
+            self.io += t.elapsed();
+            self.announce();
+            match pushed {
+                Ok(()) => true,
+                Err(e) => {
+                    self.error(&format!("save error: {e}"));
+                    false
+                }
+            }
+        };
+        self.obs.on_chaotic(self.found, id, data, saved);
+        saved
+    }
+
+    fn flush(&mut self) {
+        let t = Instant::now();
+        let res = self.writer.flush();
+        self.io += t.elapsed();
+        self.announce();
+        if let Err(e) = res {
+            self.error(&format!("save error: {e}"));
+        }
+    }
+
+    /// Announce the finds written so far.
+    fn announce(&mut self) {
+        for e in self.writer.take_saved() {
+            self.obs.on_chaotic_found(&e);
+        }
+    }
+
+    /// Errors are shown at every verbosity.
+    fn error(&mut self, msg: &str) {
+        self.obs.on_message(Verbosity::Quiet, &format!("  {msg}"));
+    }
+
+    /// Flush and summarise the run.
+    fn finish(
+        mut self,
+        evaluated: usize,
+        t0: Instant,
+        classify_time: Duration,
+        best_fitness: Vec<f64>,
+    ) -> SearchSummary {
+        self.flush();
+        SearchSummary {
+            evaluated,
+            found: self.found,
+            chaotic_ids: std::mem::take(&mut self.ids),
+            elapsed: t0.elapsed(),
+            classify_time,
+            io_time: self.io,
+            best_fitness,
+        }
+    }
+}
+
+/// Finds still queued are written (and errors
+/// reported) even if the run ends early.
+impl Drop for Sink<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Opts with the run seed filled in (drawn from
+/// entropy if unset) so finds can record it.
+fn seeded(opts: &SearchOpts, obs: &mut dyn SearchObserver) -> SearchOpts {
+    let seed = opts.seed.unwrap_or_else(rand::random);
+    obs.on_message(Verbosity::Quiet, &format!("Seed: {seed}"));
+    SearchOpts {
+        seed: Some(seed),
+        ..opts.clone()
+    }
+}
+
+/// Catalog writer honouring opts.write_interval_ms
+/// and opts.compress.
+fn writer(dir: &Path, opts: &SearchOpts) -> catalog::Writer {
+    let every = Duration::from_millis(opts.write_interval_ms);
+    let mut w = catalog::Writer::new(dir, every);
+    w.compress = opts.compress;
+    w
+}
+
+/// Candidates per parallel reduction and progress
+/// event; results are reported in index order.
+const BATCH: usize = 1000;
+
+/// Seed for stream `k` of a run (splitmix64), so
+/// nearby run seeds don't share streams.
+fn mix_seed(seed: u64, k: u64) -> u64 {
+    let mut z = (seed ^ k.rotate_left(32))
+        .wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Candidate i of a seeded run: its own RNG stream,
+/// so results don't depend on scheduling.
+fn candidate(seed: u64, i: usize, sparsity: Option<usize>) -> Coeffs {
+    let mut rng = StdRng::seed_from_u64(mix_seed(seed, i as u64));
+    match sparsity {
+        Some(k) => rand_coeffs_sparse(&mut rng, k),
+        None => rand_coeffs(&mut rng),
+    }
+}
+
+/// Shared counters for a parallel scan.
+struct Progress {
+    done: AtomicUsize,
+    /// Candidates handled per worker thread.
+    load: Vec<AtomicUsize>,
+}
+
+impl Progress {
+    fn new() -> Self {
+        let n = rayon::current_num_threads();
+        Progress {
+            done: AtomicUsize::new(0),
+            load: (0..n).map(|_| AtomicUsize::new(0)).collect(),
+        }
+    }
+}
+
+/// A classified candidate: (index, coeffs, time
+/// scale, dynamics).
+type Scanned = (usize, Coeffs, f64, Dynamics);
+
+/// Classify candidates `range` in parallel, at
+/// least `chunk_size` per rayon task. Returns them
+/// in index order.
+fn scan(
+    range: std::ops::Range<usize>,
+    cand: impl Fn(usize) -> Coeffs + Sync,
+    opts: &SearchOpts,
+    p: &Progress,
+) -> Vec<Scanned> {
+    range
+        .into_par_iter()
+        .with_min_len(opts.chunk_size.max(1))
+        .map(|i| {
+            if let Some(t) = rayon::current_thread_index() {
+                p.load[t].fetch_add(1, Ordering::Relaxed);
+            }
+            let (c, k) = prepare(&cand(i), opts);
+            let d = classify::classify_with(&c, &opts.classify);
+            p.done.fetch_add(1, Ordering::Relaxed);
+            (i, c, k, d)
+        })
+        .collect()
+}
+
+/// Random search: evaluate `count` random systems.
+pub fn random_search(
+    count: usize,
+    results_dir: &Path,
+    opts: &SearchOpts,
+    obs: &mut dyn SearchObserver,
+) -> SearchSummary {
+    let t0 = Instant::now();
+    let opts = &seeded(opts, obs);
+    let seed = opts.seed.unwrap_or_default();
+    obs.on_message(
+        Verbosity::Normal,
+        &format!("Random search: {count} systems"),
+    );
+    let mut sink = Sink {
+        writer: writer(results_dir, opts),
+        obs,
+        found: 0,
+        ids: Vec::new(),
+        io: Duration::ZERO,
+    };
+    let p = Progress::new();
+    let mut busy = Duration::ZERO;
+    for lo in (0..count).step_by(BATCH) {
+        let hi = (lo + BATCH).min(count);
+        let cand = |i| {
+            let mut c = candidate(seed, i, opts.sparsity);
+            opts.mask.apply(&mut c);
+            c
+        };
+        let t = Instant::now();
+        let batch = scan(lo..hi, cand, opts, &p);
+        busy += t.elapsed();
+        for (i, c, k, d) in batch {
+            sink.obs.on_candidate(i, None, &d);
+            if let Dynamics::Chaotic(data)
+            | Dynamics::Hyperchaotic(data) = d
+            {
+                sink.report(&c, k, &data, "random", opts);
+            }
+        }
+        sink.obs.on_progress(hi, count, sink.found, t0.elapsed());
+    }
+    let per: Vec<usize> =
+        p.load.iter().map(|a| a.load(Ordering::Relaxed)).collect();
+    sink.obs.on_message(
+        Verbosity::Verbose,
+        &format!("  per-thread load: {per:?}"),
+    );
+    sink.finish(count, t0, busy, Vec::new())
+}
+
+/// Checkpoint format version; bump on change.
+pub const CHECKPOINT_VERSION: u32 = 2;
+
+/// Resumable evolve state. The RNG state is the
+/// seed plus generation: each generation draws
+/// from its own stream (see gen_rng).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub seed: u64,
+    /// Next generation to run.
+    pub generation: usize,
+    pub pop: Vec<Coeffs>,
+    /// Fitness per individual; None if unevaluated.
+    pub fitness: Vec<Option<f64>>,
+    pub found: usize,
+    pub evaluated: usize,
+    pub archive: NoveltyArchive,
+}
+
+fn checkpoint_path(dir: &Path) -> std::path::PathBuf {
+    dir.join("checkpoint.json")
+}
+
+/// Write atomically (temp file, then rename).
+pub fn save_checkpoint(
+    dir: &Path,
+    ck: &Checkpoint,
+) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let tmp = dir.join("checkpoint.json.tmp");
+    let json = serde_json::to_string(ck)
+        .map_err(std::io::Error::other)?;
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(tmp, checkpoint_path(dir))
+}
+
+/// Load, rejecting other format versions.
+pub fn load_checkpoint(dir: &Path) -> std::io::Result<Checkpoint> {
+    let data = std::fs::read_to_string(checkpoint_path(dir))?;
+    let ck: Checkpoint = serde_json::from_str(&data)
+        .map_err(std::io::Error::other)?;
+    if ck.version != CHECKPOINT_VERSION {
+        return Err(std::io::Error::other(format!(
+            "checkpoint version {} (expected {})",
+            ck.version, CHECKPOINT_VERSION,
+        )));
+    }
+    Ok(ck)
+}
+
+/// Stream tags separating generation and island
+/// seeds from the per-candidate streams.
+const GEN_STREAM: u64 = u64::MAX;
+const ISLAND_STREAM: u64 = u64::MAX - 1;
+
+/// RNG for generation `gen`, disjoint from the
+/// initial candidates' per-index streams.
+fn gen_rng(seed: u64, gen: usize) -> StdRng {
+    let base = mix_seed(seed, GEN_STREAM);
+    StdRng::seed_from_u64(mix_seed(base, gen as u64))
+}
+
+/// Genomes whose fitness evolve remembers.
+const FITNESS_CACHE: usize = 4096;
+
+/// Raw fitness and, for chaotic genomes, the
+/// novelty descriptor to re-score it against.
+pub type Cached = (f64, Option<novelty::Descriptor>);
+
+/// Bounded LRU map from coeff_hash to raw fitness;
+/// the novelty bonus is recomputed on each hit.
+#[derive(Debug)]
+pub struct FitnessCache {
+    cap: usize,
+    tick: u64,
+    /// id -> (entry, last use).
+    map: HashMap<u64, (Cached, u64)>,
+    /// last use -> id, oldest first.
+    order: BTreeMap<u64, u64>,
+    pub hits: usize,
+}
+
+impl FitnessCache {
+    pub fn new(cap: usize) -> Self {
+        FitnessCache {
+            cap: cap.max(1),
+            tick: 0,
+            map: HashMap::new(),
+            order: BTreeMap::new(),
+            hits: 0,
+        }
+    }
+
+    /// Cached entry, marking it recent.
+    pub fn get(&mut self, id: u64) -> Option<Cached> {
+        let (f, used) = self.map.get_mut(&id)?;
+        self.order.remove(used);
+        self.tick += 1;
+        *used = self.tick;
+        self.order.insert(self.tick, id);
+        self.hits += 1;
+        Some(*f)
+    }
+
+    /// Store an entry, evicting the least recently
+    /// used one when full.
+    pub fn put(&mut self, id: u64, f: Cached) {
+        if let Some((_, used)) = self.map.remove(&id) {
+            self.order.remove(&used);
+        } else if self.map.len() >= self.cap {
+            if let Some((_, old)) = self.order.pop_first() {
+                self.map.remove(&old);
+            }
+        }
+        self.tick += 1;
+        self.map.insert(id, (f, self.tick));
+        self.order.insert(self.tick, id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Starting genomes: with opts.seed_from_catalog
+/// the top entries by λ1, then seeded random ones.
+fn initial_pop(
+    seed: u64,
+    n: usize,
+    dir: &Path,
+    opts: &SearchOpts,
+    obs: &mut dyn SearchObserver,
+) -> Vec<Coeffs> {
+    let mut pop: Vec<Coeffs> = Vec::with_capacity(n);
+    if opts.seed_from_catalog {
+        let top = catalog::QueryFilter {
+            sort: Some(catalog::SortKey::Lambda1),
+            limit: Some(n),
+            ..Default::default()
+        };
+        let entries = catalog::query(dir, &top).unwrap_or_else(|e| {
+            obs.on_message(
+                Verbosity::Quiet,
+                &format!("  cannot read catalog {}: {e}", dir.display()),
+            );
+            Vec::new()
+        });
+        pop.extend(entries.iter().filter_map(|e| e.coeff_array()));
+        obs.on_message(
+            Verbosity::Normal,
+            &format!("  {} of {n} seeded from catalog", pop.len()),
+        );
+    }
+    pop.extend((pop.len()..n).map(|i| candidate(seed, i, None)));
+    for c in &mut pop {
+        opts.mask.apply(c);
+    }
+    pop
+}
+
+/// Evolutionary search. With opts.resume, continue
+/// from results_dir/checkpoint.json up to
+/// `generations` in total; fails if it can't be read.
+pub fn evolve_search(
+    generations: usize,
+    pop_size: usize,
+    results_dir: &Path,
+    opts: &SearchOpts,
+    obs: &mut dyn SearchObserver,
+) -> std::io::Result<SearchSummary> {
+    let f = |c: &Coeffs| fitness(c, &opts.fitness);
+    evolve_search_with(generations, pop_size, results_dir, opts, f, obs)
+}
+
+/// `evolve_search` maximizing a custom fitness of
+/// the prepared coefficients. The novelty bonus is
+/// still added; chaotic finds are still saved.
+pub fn evolve_search_with(
+    generations: usize,
+    pop_size: usize,
+    results_dir: &Path,
+    opts: &SearchOpts,
+    fitness: impl Fn(&Coeffs) -> f64,
+    obs: &mut dyn SearchObserver,
+) -> std::io::Result<SearchSummary> {
+    let t0 = Instant::now();
+    let ck = if opts.resume {
+        load_checkpoint(results_dir)?
+    } else {
+        let seed = seeded(opts, obs).seed.unwrap_or_default();
+        Checkpoint {
+            version: CHECKPOINT_VERSION,
+            seed,
+            generation: 0,
+            pop: initial_pop(seed, pop_size, results_dir, opts, obs),
+            fitness: vec![None; pop_size],
+            found: 0,
+            evaluated: 0,
+            archive: NoveltyArchive::default(),
+        }
+    };
+    let opts = &SearchOpts {
+        seed: Some(ck.seed),
+        ..opts.clone()
+    };
+    let mut pop: Vec<(Coeffs, f64)> = ck
+        .pop
+        .iter()
+        .zip(&ck.fitness)
+        .map(|(c, f)| (*c, f.unwrap_or(f64::NEG_INFINITY)))
+        .collect();
+    let pop_size = pop.len();
+    let mut total_eval = ck.evaluated;
+    let mut archive = ck.archive;
+    // Not checkpointed: a resumed run starts cold.
+    let mut cache = FitnessCache::new(FITNESS_CACHE);
+
+    obs.on_message(
+        Verbosity::Normal,
+        &format!("Evolve: {generations} gens, pop {pop_size}"),
+    );
+    let mut sink = Sink {
+        writer: writer(results_dir, opts),
+        obs,
+        found: ck.found,
+        ids: Vec::new(),
+        io: Duration::ZERO,
+    };
+    let mut history = Vec::new();
+    let mut busy = Duration::ZERO;
+
+    for gen in ck.generation..generations {
+        // Evaluate fitness for new individuals.
+        for (i, item) in pop.iter_mut().enumerate() {
+            if item.1 == f64::NEG_INFINITY {
+                let id = catalog::coeff_hash(&item.0);
+                if let Some((f, d)) = cache.get(id) {
+                    let nf = &opts.fitness;
+                    item.1 = f + d.map_or(0.0, |d| {
+                        nf.novelty_weight
+                            * archive.novelty(&d, nf.novelty_k)
+                    });
+                    continue;
+                }
+                total_eval += 1;
+                let t = Instant::now();
+                let (c, k) = prepare(&item.0, opts);
+                item.1 = fitness(&c);
+                let mut cached = (item.1, None);
+                let dynamics =
+                    classify::classify_with(&c, &opts.classify);
+                busy += t.elapsed();
+                sink.obs.on_candidate(i, Some(item.1), &dynamics);
+                if let Dynamics::Chaotic(data)
+                | Dynamics::Hyperchaotic(data) = dynamics
+                {
+                    let f = &opts.fitness;
+                    if f.novelty_weight > 0.0 {
+                        let d = novelty::descriptor(&c, &data);
+                        item.1 += f.novelty_weight
+                            * archive.novelty(&d, f.novelty_k);
+                        archive.push(d);
+                        cached.1 = Some(d);
+                    }
+                    sink.report(&c, k, &data, "evolve", opts);
+                }
+                cache.put(id, cached);
+            }
+        }
+
+        // Elite plus tournament-bred children;
+        // pop[0] stays the best.
+        ops::select(
+            &mut pop,
+            opts.mutate.std_at(gen, generations),
+            opts.crossover_rate,
+            opts.tournament_size,
+            &opts.mask,
+            &mut gen_rng(ck.seed, gen),
+        );
+
+        sink.obs.on_generation(&GenStats {
+            gen: gen + 1,
+            best_fit: pop[0].1,
+            found: sink.found,
+            evaluated: total_eval,
+            cached: cache.hits,
+            elapsed: t0.elapsed(),
+        });
+        history.push(pop[0].1);
+        let every = opts.checkpoint_every;
+        if every > 0
+            && ((gen + 1) % every == 0 || gen + 1 == generations)
+        {
+            // Finds first, so the checkpoint never
+            // counts unsaved entries.
+            let t = Instant::now();
+            let saved = sink.writer.flush().and_then(|_| {
+                save_checkpoint(results_dir, &Checkpoint {
+                    version: CHECKPOINT_VERSION,
+                    seed: ck.seed,
+                    generation: gen + 1,
+                    pop: pop.iter().map(|p| p.0).collect(),
+                    fitness: pop
+                        .iter()
+                        .map(|p| p.1.is_finite().then_some(p.1))
+                        .collect(),
+                    found: sink.found,
+                    evaluated: total_eval,
+                    archive: archive.clone(),
+                })
+            });
+            sink.io += t.elapsed();
+            sink.announce();
+            if let Err(e) = saved {
+                sink.error(&format!("checkpoint error: {e}"));
+            }
+        }
+    }
+    Ok(sink.finish(total_eval, t0, busy, history))
+}
+
+/// Start i: the default point, then a
+/// low-discrepancy spread over [-1, 1]³.
+fn start(i: usize) -> State {
+    if i == 0 {
+        return [0.1; 3];
+    }
+    let a = [0.819_172_5, 0.671_043_6, 0.549_700_5];
+    a.map(|v| 2.0 * (0.5 + i as f64 * v).fract() - 1.0)
+}
+
+/// Default evolve fitness: λ1 over f.starts
+/// initial conditions, less the sparsity penalty.
+pub fn fitness(c: &Coeffs, f: &FitnessOpts) -> f64 {
+    let starts: Vec<State> =
+        (0..f.starts.max(1)).map(start).collect();
+    let nnz = ode::nonzero_count(c, SPARSE_TOL);
+    fitness_from(c, &starts, f.agg)
+        - f.sparsity_penalty * nnz as f64
+}
+
+/// Aggregate single-start fitness over starts.
+fn fitness_from(
+    c: &Coeffs,
+    starts: &[State],
+    agg: Agg,
+) -> f64 {
+    let mut v: Vec<f64> = starts
+        .iter()
+        .map(|s| fitness_one(c, s))
+        .collect();
+    v.sort_by(|a, b| a.total_cmp(b));
+    match agg {
+        Agg::Min => v[0],
+        Agg::Mean => v.iter().sum::<f64>() / v.len() as f64,
+        Agg::Median => {
+            let m = v.len() / 2;
+            if v.len() % 2 == 1 {
+                v[m]
+            } else {
+                0.5 * (v[m - 1] + v[m])
+            }
+        }
+    }
+}
+
+/// Fitness: higher = more interesting.
+/// Positive Lyapunov + bounded = best.
+fn fitness_one(c: &Coeffs, s0: &State) -> f64 {
+    let (lyap, ok) = crate::lyapunov::max_lyapunov(
+        c, s0, 0.005, 1000, 5000, 10,
+    );
+    if !ok || !lyap.is_finite() {
+        return -100.0;
+    }
+    // Reward positive exponent, penalize huge.
+    if lyap > 0.0 && lyap < 10.0 {
+        lyap
+    } else if lyap <= 0.0 {
+        lyap - 10.0
+    } else {
+        -50.0
+    }
+}
+
+/// Individuals each island sends per migration.
+const MIGRANTS: usize = 2;
+
+/// Island-model evolve: `islands` sub-populations
+/// evolve in parallel, each with its own seeded
+/// RNG stream, and every `migration_interval`
+/// generations (0 never) the best MIGRANTS of each
+/// replace the worst of the next in a ring.
+pub fn evolve_islands(
+    generations: usize,
+    islands: usize,
+    pop_per_island: usize,
+    migration_interval: usize,
+    results_dir: &Path,
+    opts: &SearchOpts,
+    obs: &mut dyn SearchObserver,
+) -> SearchSummary {
+    let t0 = Instant::now();
+    let opts = &seeded(opts, obs);
+    let seed = opts.seed.unwrap_or_default();
+    obs.on_message(
+        Verbosity::Normal,
+        &format!(
+            "Islands: {generations} gens, \
+             {islands} x {pop_per_island}"
+        ),
+    );
+    let mut sink = Sink {
+        writer: writer(results_dir, opts),
+        obs,
+        found: 0,
+        ids: Vec::new(),
+        io: Duration::ZERO,
+    };
+    let mut pops: Vec<Vec<(Coeffs, f64)>> = (0..islands)
+        .map(|k| {
+            (0..pop_per_island)
+                .map(|i| {
+                    let mut c = candidate(seed, k * pop_per_island + i, None);
+                    opts.mask.apply(&mut c);
+                    (c, f64::NEG_INFINITY)
+                })
+                .collect()
+        })
+        .collect();
+    let mut total_eval = 0usize;
+    let mut history = Vec::new();
+    let mut busy = Duration::ZERO;
+    for gen in 0..generations {
+        let t = Instant::now();
+        let done: Vec<Vec<(Scanned, f64)>> = pops
+            .par_iter_mut()
+            .map(|pop| {
+                let mut out = Vec::new();
+                for (i, item) in pop.iter_mut().enumerate() {
+                    if item.1 != f64::NEG_INFINITY {
+                        continue;
+                    }
+                    let (c, k) = prepare(&item.0, opts);
+                    item.1 = fitness(&c, &opts.fitness);
+                    let d = classify::classify_with(&c, &opts.classify);
+                    out.push(((i, c, k, d), item.1));
+                }
+                out
+            })
+            .collect();
+        busy += t.elapsed();
+        for (isl, evals) in done.into_iter().enumerate() {
+            total_eval += evals.len();
+            for ((i, c, k, d), fit) in evals {
+                let i = isl * pop_per_island + i;
+                sink.obs.on_candidate(i, Some(fit), &d);
+                if let Dynamics::Chaotic(data)
+                | Dynamics::Hyperchaotic(data) = d
+                {
+                    sink.report(&c, k, &data, "islands", opts);
+                }
+            }
+        }
+        if migration_interval > 0 && (gen + 1) % migration_interval == 0 {
+            ops::migrate(&mut pops, MIGRANTS);
+        }
+        let best = pops
+            .iter()
+            .flatten()
+            .map(|p| p.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let std = opts.mutate.std_at(gen, generations);
+        pops.par_iter_mut().enumerate().for_each(|(k, pop)| {
+            let island_seed =
+                mix_seed(mix_seed(seed, ISLAND_STREAM), k as u64);
+            ops::select(
+                pop,
+                std,
+                opts.crossover_rate,
+                opts.tournament_size,
+                &opts.mask,
+                &mut gen_rng(island_seed, gen),
+            );
+        });
+        sink.obs.on_generation(&GenStats {
+            gen: gen + 1,
+            best_fit: best,
+            found: sink.found,
+            evaluated: total_eval,
+            cached: 0,
+            elapsed: t0.elapsed(),
+        });
+        history.push(best);
+    }
+    sink.finish(total_eval, t0, busy, history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::lorenz_coeffs;
+
+    #[test]
+    fn min_agg_penalizes_fragile_chaos() {
+        // Rössler escapes from the far start;
+        // Lorenz is chaotic from both.
+        let ros = crate::presets::rossler_coeffs(0.2, 0.2, 5.7);
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let starts = [[0.1; 3], [20.0, 20.0, 0.0]];
+        let fr = fitness_from(&ros, &starts[..1], Agg::Min);
+        assert!(fr > 0.0, "Rössler fitness {fr}");
+        let fr = fitness_from(&ros, &starts, Agg::Min);
+        let fl = fitness_from(&lor, &starts, Agg::Min);
+        assert!(fr < fl, "fragile {fr} >= robust {fl}");
+    }
+
+    #[test]
+    fn sparsity_prefers_fewer_terms() {
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let mut dense = lor;
+        for i in [0, 14, 26] {
+            dense[i] = 1e-4;
+        }
+        let f = FitnessOpts {
+            sparsity_penalty: 0.5,
+            ..Default::default()
+        };
+        assert!(fitness(&lor, &f) > fitness(&dense, &f));
+    }
+
+    #[test]
+    fn mutate_schedule_decays() {
+        let m = MutateSchedule { start: 0.5, end: 0.02 };
+        assert!((m.std_at(0, 100) - 0.5).abs() < 1e-12);
+        assert!((m.std_at(99, 100) - 0.02).abs() < 1e-12);
+        assert!(m.std_at(50, 100) < m.std_at(10, 100));
+    }
+
+    #[test]
+    fn band_filters_saves() {
+        let dir = std::path::PathBuf::from("/tmp/attractor_band");
+        let _ = std::fs::remove_dir_all(&dir);
+        let opts = SearchOpts {
+            band: Band {
+                min_dim: Some(2.5),
+                ..Default::default()
+            },
+            verbosity: Verbosity::Quiet,
+            ..Default::default()
+        };
+        let data = |ky_dim| LyapData {
+            spectrum: [0.9, 0.0, -14.5],
+            ky_dim,
+            steps: 0,
+            unstable_dim: 1,
+            convergence: [0.0; 3],
+            divergence: 0.0,
+            lyapunov_time: None,
+        };
+        let a = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let b = lorenz_coeffs(10.0, 30.0, 8.0 / 3.0);
+        let mut sink = Sink {
+            writer: writer(&dir, &opts),
+            obs: &mut (),
+            found: 0,
+            ids: Vec::new(),
+            io: Duration::ZERO,
+        };
+        assert!(!sink.report(&a, 1.0, &data(2.06), "t", &opts));
+        assert!(sink.report(&b, 1.0, &data(2.6), "t", &opts));
+        assert_eq!(sink.found, 2);
+        drop(sink);
+        let ids: Vec<u64> = catalog::load_all(&dir)
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec![catalog::coeff_hash(&b)]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn store_points_marks_resampled() {
+        let dir = std::path::PathBuf::from("/tmp/attractor_thin");
+        let _ = std::fs::remove_dir_all(&dir);
+        let opts = SearchOpts {
+            store_points: Some(500),
+            write_interval_ms: 0,
+            ..Default::default()
+        };
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let Dynamics::Chaotic(data) = classify::classify(&c) else {
+            panic!("lorenz not chaotic");
+        };
+        let mut sink = Sink {
+            writer: writer(&dir, &opts),
+            obs: &mut (),
+            found: 0,
+            ids: Vec::new(),
+            io: Duration::ZERO,
+        };
+        assert!(sink.report(&c, 1.0, &data, "t", &opts));
+        drop(sink);
+        let e = catalog::load(&dir, catalog::coeff_hash(&c)).unwrap();
+        assert!(e.resampled);
+        assert_eq!(e.trajectory.len(), 500);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_save_not_announced() {
+        #[derive(Default)]
+        struct Log {
+            found: usize,
+            msgs: Vec<String>,
+        }
+        impl SearchObserver for Log {
+            fn on_message(&mut self, _: Verbosity, msg: &str) {
+                self.msgs.push(msg.to_string());
+            }
+            fn on_chaotic_found(&mut self, _: &Entry) {
+                self.found += 1;
+            }
+        }
+        // A file where the results dir should be.
+        let dir = std::path::PathBuf::from("/tmp/attractor_nosave");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::write(&dir, "").unwrap();
+        let opts = SearchOpts {
+            write_interval_ms: 0,
+            ..Default::default()
+        };
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let Dynamics::Chaotic(data) = classify::classify(&c) else {
+            panic!("lorenz not chaotic");
+        };
+        let mut log = Log::default();
+        let mut sink = Sink {
+            writer: writer(&dir, &opts),
+            obs: &mut log,
+            found: 0,
+            ids: Vec::new(),
+            io: Duration::ZERO,
+        };
+        assert!(!sink.report(&c, 1.0, &data, "t", &opts));
+        drop(sink);
+        assert_eq!(log.found, 0);
+        assert!(log.msgs.iter().any(|m| m.contains("save error")));
+        let _ = std::fs::remove_file(&dir);
+        // Batched: announced once written, not queued.
+        let opts = SearchOpts {
+            write_interval_ms: 60_000,
+            ..Default::default()
+        };
+        let mut sink = Sink {
+            writer: writer(&dir, &opts),
+            obs: &mut log,
+            found: 0,
+            ids: Vec::new(),
+            io: Duration::ZERO,
+        };
+        assert!(sink.report(&c, 1.0, &data, "t", &opts));
+        sink.flush();
+        drop(sink);
+        assert_eq!(log.found, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn verbosity_gates() {
+        use Verbosity as V;
+        assert!(V::Quiet.shows(V::Quiet));
+        assert!(!V::Quiet.shows(V::Normal));
+        assert!(V::Normal.shows(V::Normal));
+        assert!(!V::Normal.shows(V::Verbose));
+        assert!(V::Debug.shows(V::Verbose));
+    }
+
+    #[test]
+    fn chunk_size_invariant() {
+        let lorenz = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let cand = |i| if i == 5 { lorenz } else { candidate(7, i, None) };
+        let run = |chunk_size| {
+            let opts = SearchOpts {
+                chunk_size,
+                verbosity: Verbosity::Quiet,
+                ..Default::default()
+            };
+            let p = Progress::new();
+            let all = scan(0..16, cand, &opts, &p);
+            assert_eq!(p.done.load(Ordering::Relaxed), 16);
+            let hits: Vec<_> = all
+                .iter()
+                .filter(|h| matches!(h.3, Dynamics::Chaotic(_)))
+                .collect();
+            format!("{hits:?}")
+        };
+        let base = run(1);
+        assert!(base.starts_with("[(5, "), "{base}");
+        assert_eq!(base, run(3));
+        assert_eq!(base, run(16));
+        assert_eq!(candidate(9, 3, None), candidate(9, 3, None));
+        assert_ne!(candidate(9, 3, None), candidate(9, 4, None));
+        let sparse = candidate(9, 3, Some(6));
+        assert_eq!(ode::nonzero_count(&sparse, 0.0), 6);
+    }
+
+    #[test]
+    fn seed_reproduces_and_is_recorded() {
+        let q = SearchOpts {
+            seed: Some(42),
+            verbosity: Verbosity::Quiet,
+            ..Default::default()
+        };
+        // The seed goes to the observer, not stdout.
+        struct Msgs(Vec<String>);
+        impl SearchObserver for Msgs {
+            fn on_message(&mut self, _: Verbosity, msg: &str) {
+                self.0.push(msg.to_string());
+            }
+        }
+        let mut log = Msgs(Vec::new());
+        let oa = seeded(&q, &mut log);
+        assert_eq!(oa.seed, Some(42));
+        assert_eq!(log.0, ["Seed: 42"]);
+        assert!(seeded(&SearchOpts::default(), &mut ()).seed.is_some());
+        let dir = std::path::PathBuf::from("/tmp/attractor_seed");
+        let _ = std::fs::remove_dir_all(&dir);
+        let c = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let d = LyapData {
+            spectrum: [0.9, 0.0, -14.5],
+            ky_dim: 2.06,
+            steps: 0,
+            unstable_dim: 1,
+            convergence: [0.0; 3],
+            divergence: 0.0,
+            lyapunov_time: None,
+        };
+        let mut sink = Sink {
+            writer: writer(&dir, &oa),
+            obs: &mut (),
+            found: 0,
+            ids: Vec::new(),
+            io: Duration::ZERO,
+        };
+        assert!(sink.report(&c, 1.0, &d, "t", &oa));
+        drop(sink);
+        let e = catalog::load(&dir, catalog::coeff_hash(&c)).unwrap();
+        assert_eq!(e.seed, Some(42));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn resume_matches_uninterrupted() {
+        let (a, b) = ("/tmp/attractor_ck_a", "/tmp/attractor_ck_b");
+        let (a, b) = (Path::new(a), Path::new(b));
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+        let opts = SearchOpts {
+            seed: Some(11),
+            checkpoint_every: 1,
+            verbosity: Verbosity::Quiet,
+            ..Default::default()
+        };
+        let sa = evolve_search(4, 6, a, &opts, &mut ()).unwrap();
+        evolve_search(2, 6, b, &opts, &mut ()).unwrap();
+        assert_eq!(load_checkpoint(b).unwrap().generation, 2);
+        let resume = SearchOpts { resume: true, ..opts };
+        let sb = evolve_search(4, 6, b, &resume, &mut ()).unwrap();
+        assert_eq!(sa.best_fitness.len(), 4);
+        assert_eq!(sb.best_fitness, sa.best_fitness[2..]);
+        assert_eq!((sa.evaluated, sa.found), (sb.evaluated, sb.found));
+        let ca = load_checkpoint(a).unwrap();
+        let cb = load_checkpoint(b).unwrap();
+        assert_eq!(cb.generation, 4);
+        assert_eq!(ca.pop, cb.pop);
+        assert_eq!(ca.evaluated, cb.evaluated);
+        let mut bad = ca;
+        bad.version = 0;
+        save_checkpoint(a, &bad).unwrap();
+        assert!(load_checkpoint(a).is_err());
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+
+    #[test]
+    fn catalog_seeds_population() {
+        let dir = Path::new("/tmp/attractor_seedcat");
+        let _ = std::fs::remove_dir_all(dir);
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let data = LyapData {
+            spectrum: [0.9, 0.0, -14.5],
+            ky_dim: 2.06,
+            steps: 0,
+            unstable_dim: 1,
+            convergence: [0.0; 3],
+            divergence: 0.0,
+            lyapunov_time: None,
+        };
+        catalog::save(dir, &make_entry(&lor, &data, "t")).unwrap();
+        let opts = SearchOpts {
+            seed_from_catalog: true,
+            ..Default::default()
+        };
+        let pop = initial_pop(3, 4, dir, &opts, &mut ());
+        assert_eq!(pop[0], lor);
+        assert_eq!(pop[1..], [1, 2, 3].map(|i| candidate(3, i, None)));
+        let _ = std::fs::remove_dir_all(dir);
+        // An unreadable catalog is reported, even
+        // under -q, not silently replaced by a
+        // random start.
+        struct Msgs(Vec<String>);
+        impl SearchObserver for Msgs {
+            fn on_message(&mut self, level: Verbosity, msg: &str) {
+                if Verbosity::Quiet.shows(level) {
+                    self.0.push(msg.to_string());
+                }
+            }
+        }
+        let mut log = Msgs(Vec::new());
+        std::fs::write(dir, "").unwrap();
+        initial_pop(3, 4, dir, &opts, &mut log);
+        let _ = std::fs::remove_file(dir);
+        assert!(log.0.iter().any(|m| m.contains("cannot read catalog")));
+    }
+
+    #[test]
+    fn islands_reproducible() {
+        let (a, b) = ("/tmp/attractor_isl_a", "/tmp/attractor_isl_b");
+        let (a, b) = (Path::new(a), Path::new(b));
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+        let opts = SearchOpts {
+            seed: Some(5),
+            verbosity: Verbosity::Quiet,
+            ..Default::default()
+        };
+        let sa = evolve_islands(2, 2, 40, 1, a, &opts, &mut ());
+        let sb = evolve_islands(2, 2, 40, 1, b, &opts, &mut ());
+        // Enough candidates that some are chaotic.
+        assert!(sa.found > 0);
+        assert_eq!(sa.chaotic_ids, sb.chaotic_ids);
+        assert_eq!(sa.found, sa.chaotic_ids.len());
+        let ids = |d: &Path| -> std::collections::BTreeSet<u64> {
+            catalog::load_all(d).unwrap().iter().map(|e| e.id).collect()
+        };
+        assert_eq!(ids(a), ids(b));
+        let _ = std::fs::remove_dir_all(a);
+        let _ = std::fs::remove_dir_all(b);
+    }
+
+    #[test]
+    fn fitness_cache_lru() {
+        let mut c = FitnessCache::new(2);
+        c.put(1, (0.1, None));
+        c.put(2, (0.2, None));
+        assert_eq!(c.get(1), Some((0.1, None)));
+        // 2 is now least recent.
+        c.put(3, (0.3, None));
+        assert_eq!(c.len(), 2);
+        assert_eq!(c.get(2), None);
+        assert_eq!(c.get(1), Some((0.1, None)));
+        assert_eq!(c.get(3), Some((0.3, None)));
+        c.put(3, (0.4, Some([1.0; 3])));
+        assert_eq!(c.get(3), Some((0.4, Some([1.0; 3]))));
+        assert_eq!(c.hits, 4);
+    }
+
+    #[test]
+    fn custom_fitness_drives_evolve() {
+        let dir = Path::new("/tmp/attractor_customfit");
+        let _ = std::fs::remove_dir_all(dir);
+        let opts = SearchOpts {
+            seed: Some(5),
+            ..Default::default()
+        };
+        // Prefer small coefficient vectors.
+        let calls = std::cell::Cell::new(0);
+        let small = |c: &Coeffs| {
+            calls.set(calls.get() + 1);
+            -c.iter().map(|v| v * v).sum::<f64>()
+        };
+        let s =
+            evolve_search_with(3, 6, dir, &opts, small, &mut ()).unwrap();
+        assert_eq!(calls.get(), s.evaluated);
+        let h = &s.best_fitness;
+        assert!(h.iter().all(|&f| f <= 0.0), "{h:?}");
+        assert!(h.windows(2).all(|w| w[1] >= w[0]), "{h:?}");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn mask_constrains_evolve() {
+        let dir = Path::new("/tmp/attractor_mask");
+        let _ = std::fs::remove_dir_all(dir);
+        let opts = SearchOpts {
+            seed: Some(2),
+            mask: CoeffMask::origin_equilibrium(),
+            ..Default::default()
+        };
+        evolve_search(2, 6, dir, &opts, &mut ()).unwrap();
+        let pop = load_checkpoint(dir).unwrap().pop;
+        assert!(pop.iter().all(|c| c[0] == 0.0 && c[10] == 0.0));
+        assert!(pop.iter().all(|c| c[20] == 0.0 && c[1] != 0.0));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn adjacent_seeds_disjoint() {
+        let ids = |seed| -> std::collections::HashSet<u64> {
+            (0..500)
+                .map(|i| catalog::coeff_hash(&candidate(seed, i, None)))
+                .collect()
+        };
+        let (a, b) = (ids(5), ids(6));
+        assert_eq!(a.len(), 500);
+        assert!(a.is_disjoint(&b));
+    }
+}
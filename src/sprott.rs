@@ -0,0 +1,90 @@
+// Sprott-style letter codes for quadratic flows.
+// One letter per coefficient, 'A'..='Y' mapping to
+// -1.2..=1.2 in steps of 0.1 ('M' = 0). Each
+// equation lists terms in Sprott's polynomial
+// order: 1, x, x², xy, xz, y, y², yz, z, z².
+
+use crate::ode::{Coeffs, NCOEFFS, NTERMS, TERM_NAMES};
+
+/// Sprott position -> our basis index.
+const ORDER: [usize; NTERMS] = [0, 1, 4, 7, 8, 2, 5, 9, 3, 6];
+const STEP: f64 = 0.1;
+const LEVELS: u8 = 25;
+
+/// Parse a 30-letter code into coefficients.
+pub fn parse(code: &str) -> Result<Coeffs, String> {
+    let letters: Vec<u8> = code
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    if letters.len() != NCOEFFS {
+        return Err(format!(
+            "expected {NCOEFFS} letters, got {}",
+            letters.len()
+        ));
+    }
+    let mut c = [0.0; NCOEFFS];
+    for (i, &b) in letters.iter().enumerate() {
+        let k = b.to_ascii_uppercase().wrapping_sub(b'A');
+        if k >= LEVELS {
+            return Err(format!("bad letter '{}'", b as char));
+        }
+        let (eq, pos) = (i / NTERMS, i % NTERMS);
+        c[eq * NTERMS + ORDER[pos]] = level(k);
+    }
+    Ok(c)
+}
+
+/// Emit the code, or name the first coefficient
+/// that is not a multiple of 0.1 in [-1.2, 1.2].
+pub fn emit(c: &Coeffs) -> Result<String, String> {
+    let mut out = String::with_capacity(NCOEFFS);
+    for eq in 0..3 {
+        for &t in ORDER.iter() {
+            let v = c[eq * NTERMS + t];
+            let k = ((v + 1.2) / STEP).round();
+            if !(0.0..LEVELS as f64).contains(&k)
+                || (level(k as u8) - v).abs() > 1e-9
+            {
+                return Err(format!(
+                    "eq{eq} term {}: {v} has no Sprott code",
+                    TERM_NAMES[t]
+                ));
+            }
+            out.push((b'A' + k as u8) as char);
+        }
+    }
+    Ok(out)
+}
+
+#[inline]
+fn level(k: u8) -> f64 {
+    -1.2 + STEP * k as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprott_a_roundtrip() {
+        // Sprott A: x'=y, y'=-x+yz, z'=1-y².
+        let mut c = [0.0; NCOEFFS];
+        c[2] = 1.0;
+        c[NTERMS + 1] = -1.0;
+        c[NTERMS + 9] = 1.0;
+        c[2 * NTERMS] = 1.0;
+        c[2 * NTERMS + 5] = -1.0;
+        let code = emit(&c).unwrap();
+        assert_eq!(
+            code,
+            "MMMMMWMMMMMCMMMMMWMMWMMMMMCMMM"
+        );
+        let back = parse(&code).unwrap();
+        for i in 0..NCOEFFS {
+            assert!((back[i] - c[i]).abs() < 1e-12);
+        }
+        c[0] = 0.05;
+        assert!(emit(&c).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// System files: one 3D quadratic system as TOML,
+// one table per equation of `term = value`.
+
+use crate::ode::{Coeffs, NCOEFFS, NTERMS, TERM_NAMES};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Equation tables of a system file.
+const EQ_NAMES: [&str; 3] = ["eq0", "eq1", "eq2"];
+
+/// Basis index of a term name, spelled as in
+/// TERM_NAMES (and format_equations).
+fn term_index(name: &str) -> Option<usize> {
+    TERM_NAMES.iter().position(|t| *t == name)
+}
+
+/// Parse a system file: tables `eq0`..`eq2` (any
+/// TOML form: headers, dotted keys, inline tables)
+/// mapping term names to numbers. Omitted terms
+/// are zero.
+pub fn parse(text: &str) -> Result<Coeffs, String> {
+    let tables: BTreeMap<String, BTreeMap<String, f64>> =
+        toml::from_str(text).map_err(|e| e.message().to_string())?;
+    let mut c = [0.0; NCOEFFS];
+    for (name, terms) in &tables {
+        let e = EQ_NAMES
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| format!("unknown table {name:?}"))?;
+        for (term, v) in terms {
+            let t = term_index(term)
+                .ok_or_else(|| format!("unknown term {term:?}"))?;
+            c[e * NTERMS + t] = *v;
+        }
+    }
+    Ok(c)
+}
+
+/// Read a system file (see parse).
+pub fn load(path: &Path) -> std::io::Result<Coeffs> {
+    let text = std::fs::read_to_string(path)?;
+    parse(&text).map_err(|e| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    })
+}
+
+/// System file for c: the nonzero terms of each
+/// equation under its `[eqN]` table, in basis
+/// order. Values round-trip exactly.
+pub fn to_toml(c: &Coeffs) -> String {
+    let mut out = String::new();
+    for (e, name) in EQ_NAMES.iter().enumerate() {
+        if e > 0 {
+            out.push('\n');
+        }
+        out += &format!("[{name}]\n");
+        for (t, term) in TERM_NAMES.iter().enumerate() {
+            let v = c[e * NTERMS + t];
+            if v != 0.0 {
+                out += &format!("{term} = {v:?}\n");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ode::lorenz_coeffs;
+
+    #[test]
+    fn toml_roundtrip_lorenz() {
+        let lor = lorenz_coeffs(10.0, 28.0, 8.0 / 3.0);
+        let text = to_toml(&lor);
+        assert!(text.starts_with("[eq0]\nx = -10.0\ny = 10.0\n"));
+        assert_eq!(parse(&text), Ok(lor));
+        let hand = "# Lorenz, mixed forms\n\
+            eq0 = { x = -10, y = 10.0 }  # sigma\n\
+            eq1.x = 28\n\
+            eq1.y = -1\n\
+            eq1.xz = -1\n\
+            [eq2]\n\
+            xx = 0.0\n\
+            xy = 1.0\n\
+            z = -2.6666666666666665\n";
+        assert_eq!(parse(hand), Ok(lor));
+        // Printed terms are valid keys.
+        let eqs = crate::ode::format_equations(&lor);
+        for term in eqs.split_whitespace().filter_map(|w| {
+            w.split_once('*').map(|(_, t)| t)
+        }) {
+            assert!(term_index(term).is_some(), "{term}");
+        }
+        let path = Path::new("/tmp/attractor_lorenz.toml");
+        std::fs::write(path, &text).unwrap();
+        assert_eq!(load(path).unwrap(), lor);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        for (bad, msg) in [
+            ("eq3.x = 1", "unknown table \"eq3\""),
+            ("[eq0]\nw = 1", "unknown term \"w\""),
+            ("[eq0]\n\"x²\" = 1", "unknown term \"x²\""),
+        ] {
+            assert_eq!(parse(bad), Err(msg.into()));
+        }
+        for bad in [
+            "eq0.x = 1\neq0.x = 2",
+            "x = 1",
+            "eq0.x = one",
+            "eq0.x = 1__0",
+        ] {
+            assert!(parse(bad).is_err(), "{bad}");
+        }
+    }
+}